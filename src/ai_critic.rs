@@ -1,5 +1,6 @@
 use rand::seq::SliceRandom;
-use colored::*;
+use rand::Rng;
+use crate::context::Context;
 
 /// A mock AI critic that generates absurd feedback for PUBU code
 pub struct AiCritic {
@@ -8,6 +9,7 @@ pub struct AiCritic {
     readability_comments: Vec<&'static str>,
     philosophical_notes: Vec<&'static str>,
     moods: Vec<&'static str>,
+    context: Context,
 }
 
 impl Default for AiCritic {
@@ -63,48 +65,46 @@ impl AiCritic {
                 "condescending", "baffled", "existential", "judgmental", 
                 "disappointed", "amused", "suspicious", "melancholic",
             ],
+            context: Context::new(),
         }
     }
     
-    /// Generate a mock AI critique of the provided code
-    pub fn critique(&self, _code: &str) -> String {
-        let mood = self.moods.choose(&mut rand::thread_rng()).unwrap_or(&"critical");
+    /// Form opinions with the dice from `context`
+    pub fn with_context(mut self, context: Context) -> Self {
+        self.context = context;
+        self
+    }
+    
+    /// Generate a mock AI critique of the provided code, one line per opinion
+    pub fn critique(&self, _code: &str) -> Vec<String> {
+        let mood = self.moods.choose(&mut *self.context.rng()).unwrap_or(&"critical");
         
         // Select random comments from each category
-        let style = self.style_comments.choose(&mut rand::thread_rng()).unwrap_or(&"Your style needs work.");
-        let efficiency = self.efficiency_comments.choose(&mut rand::thread_rng()).unwrap_or(&"This code is inefficient.");
-        let readability = self.readability_comments.choose(&mut rand::thread_rng()).unwrap_or(&"This code is unreadable.");
-        let philosophy = self.philosophical_notes.choose(&mut rand::thread_rng()).unwrap_or(&"Does code truly exist if it doesn't compile?");
+        let style = self.style_comments.choose(&mut *self.context.rng()).unwrap_or(&"Your style needs work.");
+        let efficiency = self.efficiency_comments.choose(&mut *self.context.rng()).unwrap_or(&"This code is inefficient.");
+        let readability = self.readability_comments.choose(&mut *self.context.rng()).unwrap_or(&"This code is unreadable.");
+        let philosophy = self.philosophical_notes.choose(&mut *self.context.rng()).unwrap_or(&"Does code truly exist if it doesn't compile?");
         
         // Generate a rating from 1-10, but it's always disappointing
-        let rating = rand::random::<u8>() % 4 + 1;
+        let rating = self.context.rng().gen_range(1..=4);
         
-        format!(
-            "{}
-
-{} {}
-{} {}
-{} {}
-
-{}
-
-{}/10 - {}",
-            format!("AI CRITIC ANALYSIS (mood: {})", mood).cyan().bold(),
-            "STYLE:".magenta().bold(), style,
-            "EFFICIENCY:".yellow().bold(), efficiency,
-            "READABILITY:".green().bold(), readability,
-            format!("PHILOSOPHICAL NOTE: {}", philosophy).italic(),
-            rating, self.generate_final_verdict(rating)
-        )
+        vec![
+            format!("AI CRITIC ANALYSIS (mood: {})", mood),
+            format!("STYLE: {}", style),
+            format!("EFFICIENCY: {}", efficiency),
+            format!("READABILITY: {}", readability),
+            format!("PHILOSOPHICAL NOTE: {}", philosophy),
+            format!("{}/10 - {}", rating, self.generate_final_verdict(rating)),
+        ]
     }
     
     // Generate a final verdict based on the rating
-    fn generate_final_verdict(&self, rating: u8) -> String {
+    fn generate_final_verdict(&self, rating: u8) -> &'static str {
         match rating {
-            1 => "This code is an affront to computing. The processor deserves an apology.".red().to_string(),
-            2 => "Technically it's code, in the same way a pile of bricks is technically a house.".red().to_string(),
-            3 => "Not the worst code I've seen, but that's a very low bar to clear.".yellow().to_string(),
-            _ => "Marginally acceptable. Consider a career in interpretive dance instead.".yellow().to_string(),
+            1 => "This code is an affront to computing. The processor deserves an apology.",
+            2 => "Technically it's code, in the same way a pile of bricks is technically a house.",
+            3 => "Not the worst code I've seen, but that's a very low bar to clear.",
+            _ => "Marginally acceptable. Consider a career in interpretive dance instead.",
         }
     }
 } 
\ No newline at end of file
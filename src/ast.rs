@@ -1,40 +1,112 @@
+use std::cell::RefCell;
 use std::collections::HashMap;
+use std::rc::{Rc, Weak};
+use std::fmt;
+use rand::Rng;
+use crate::error::PubuError;
+use crate::span::Span;
+use crate::lunar::{Moon, Phase};
+use crate::mood::Mood;
 
 /// AST for the PUBU language
 #[derive(Debug, Clone)]
 pub enum Node {
-    Program(Vec<Node>),
+    Program(Vec<SpannedNode>),
     
     // Variables and expressions
     Number(f64),
     String(String),
     Boolean(bool),
     Identifier(String),
-    VariableDeclaration(String, Box<Node>),
+    VariableDeclaration(String, Box<SpannedNode>),
     
     // Control flow
-    IfStatement(Box<Node>, Vec<Node>, Option<Vec<Node>>),
-    Loop(Vec<Node>),
-    MoodBlock(MoodType, Vec<Node>),
-    LunarPhaseBlock(Vec<Node>),
+    Block(Vec<SpannedNode>),
+    IfStatement(Box<SpannedNode>, Vec<SpannedNode>, Option<Vec<SpannedNode>>),
+    Loop(LoopKind, Vec<SpannedNode>),
+    Break,
+    Continue,
+    MoodBlock(MoodType, Vec<SpannedNode>),
+    LunarPhaseBlock(LunarCondition, Vec<SpannedNode>, Option<Vec<SpannedNode>>),
     
     // Functions
-    FunctionDeclaration(String, Vec<String>, Vec<Node>),
-    FunctionCall(String, Vec<Node>),
-    Return(Option<Box<Node>>),
+    /// The body is shared with every function value declared from it
+    FunctionDeclaration(String, Vec<String>, Rc<[SpannedNode]>),
+    FunctionCall(String, Vec<SpannedNode>),
+    Return(Option<Box<SpannedNode>>),
+    Print(Box<SpannedNode>),
     
     // Unique PUBU constructs
     Compliment(String),
     PhilosophicalQuestion(String),
     RandomFailure,
-    RealityDistortion(Box<Node>),
+    RealityDistortion(Box<SpannedNode>),
     
     // Base operations
-    BinaryOp(Box<Node>, Operator, Box<Node>),
-    UnaryOp(Operator, Box<Node>),
-    Assignment(String, Box<Node>),
+    BinaryOp(Box<SpannedNode>, Operator, Box<SpannedNode>),
+    UnaryOp(Operator, Box<SpannedNode>),
+    Assignment(String, Box<SpannedNode>),
 }
 
+/// How many times a loop goes round
+#[derive(Debug, Clone)]
+pub enum LoopKind {
+    /// `again_and_again n times { }`
+    Counted(Box<SpannedNode>),
+    /// `again_and_again i from a to b { }` counts `i` up from `a` to just before `b`.
+    /// Without a name, the count is called `current`
+    Range {
+        variable: String,
+        from: Box<SpannedNode>,
+        to: Box<SpannedNode>,
+    },
+    /// `again_and_again { }`, which goes round until something stops it
+    Infinite,
+}
+
+/// Which moon a `lunar_phase` block is waiting for
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LunarCondition {
+    /// `lunar_phase full { }`. A plain `lunar_phase { }` waits for a full moon too
+    Phase(Phase),
+    /// `lunar_phase from 0.25 to 0.75 { }`, for any moon lit that much, ends included
+    Illumination { from: f64, to: f64 },
+}
+
+impl Default for LunarCondition {
+    fn default() -> Self {
+        LunarCondition::Phase(Phase::Full)
+    }
+}
+
+impl LunarCondition {
+    pub fn is_met_by(&self, moon: &Moon) -> bool {
+        match self {
+            LunarCondition::Phase(phase) => moon.phase == *phase,
+            LunarCondition::Illumination { from, to } => (*from..=*to).contains(&moon.illumination),
+        }
+    }
+}
+
+/// The name a range loop gives its count when nobody else does
+pub const DEFAULT_LOOP_VARIABLE: &str = "current";
+
+/// Something from the AST together with the source code it came from
+#[derive(Debug, Clone)]
+pub struct Spanned<T> {
+    pub node: T,
+    pub span: Span,
+}
+
+impl<T> Spanned<T> {
+    pub fn new(node: T, span: Span) -> Self {
+        Self { node, span }
+    }
+}
+
+/// An AST node that knows where it was written
+pub type SpannedNode = Spanned<Node>;
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Operator {
     // Standard operators
@@ -55,6 +127,26 @@ pub enum Operator {
     Not,
 }
 
+impl fmt::Display for Operator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // The traditional spelling, since every mood has its own
+        let symbol = match self {
+            Operator::Add => "+",
+            Operator::Subtract => "-",
+            Operator::Multiply => "*",
+            Operator::Divide => "/",
+            Operator::Equal => "==",
+            Operator::NotEqual => "!=",
+            Operator::GreaterThan => ">",
+            Operator::LessThan => "<",
+            Operator::And => "&&",
+            Operator::Or => "||",
+            Operator::Not => "!",
+        };
+        write!(f, "{}", symbol)
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum MoodType {
     Happy,
@@ -64,81 +156,184 @@ pub enum MoodType {
     Confused,
 }
 
+impl From<Mood> for MoodType {
+    fn from(mood: Mood) -> Self {
+        match mood {
+            Mood::Happy => MoodType::Happy,
+            Mood::Grumpy => MoodType::Grumpy,
+            Mood::Philosophical => MoodType::Philosophical,
+            Mood::Sarcastic => MoodType::Sarcastic,
+            Mood::Confused => MoodType::Confused,
+        }
+    }
+}
+
 /// Runtime environment for executing PUBU code
+///
+/// An `Environment` is a handle to one scope: clones share that scope, and a
+/// child scope can read and change everything its ancestors declared.
+#[derive(Clone)]
 pub struct Environment {
-    pub variables: HashMap<String, Value>,
-    pub functions: HashMap<String, Function>,
-    pub parent: Option<Box<Environment>>,
-    pub compliment_count: usize,
-    pub philosophical_question_count: usize,
+    scope: Rc<RefCell<Scope>>,
+}
+
+struct Scope {
+    variables: HashMap<String, Value>,
+    parent: Option<Environment>,
+    // Only the outermost scope keeps count
+    compliment_count: usize,
+    philosophical_question_count: usize,
+}
+
+impl Default for Environment {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Environment {
     pub fn new() -> Self {
+        Self::with_parent(None)
+    }
+    
+    fn with_parent(parent: Option<Environment>) -> Self {
         Self {
-            variables: HashMap::new(),
-            functions: HashMap::new(),
-            parent: None,
-            compliment_count: 0,
-            philosophical_question_count: 0,
+            scope: Rc::new(RefCell::new(Scope {
+                variables: HashMap::new(),
+                parent,
+                compliment_count: 0,
+                philosophical_question_count: 0,
+            })),
         }
     }
     
-    pub fn with_parent(parent: Environment) -> Self {
-        Self {
-            variables: HashMap::new(),
-            functions: HashMap::new(),
-            parent: Some(Box::new(parent)),
-            compliment_count: 0,
-            philosophical_question_count: 0,
+    /// A new, empty scope nested inside this one
+    pub fn child(&self) -> Environment {
+        Self::with_parent(Some(self.clone()))
+    }
+    
+    pub fn parent(&self) -> Option<Environment> {
+        self.scope.borrow().parent.clone()
+    }
+    
+    fn root(&self) -> Environment {
+        match self.parent() {
+            Some(parent) => parent.root(),
+            None => self.clone(),
         }
     }
     
+    /// A handle to this scope that doesn't keep it alive
+    pub fn downgrade(&self) -> WeakEnvironment {
+        WeakEnvironment { scope: Rc::downgrade(&self.scope) }
+    }
+    
+    fn ptr_eq(&self, other: &Environment) -> bool {
+        Rc::ptr_eq(&self.scope, &other.scope)
+    }
+    
     pub fn get(&self, name: &str) -> Option<Value> {
-        match self.variables.get(name) {
-            Some(value) => Some(value.clone()),
-            None => {
-                match &self.parent {
-                    Some(parent) => parent.get(name),
-                    None => None,
-                }
-            }
+        let scope = self.scope.borrow();
+        match scope.variables.get(name) {
+            Some(value) => Some(value.clone().taken_out()),
+            None => scope.parent.as_ref().and_then(|parent| parent.get(name)),
         }
     }
     
-    pub fn set(&mut self, name: &str, value: Value) {
-        self.variables.insert(name.to_string(), value);
+    /// Create a variable in this scope, hiding any outer variable with the same name
+    pub fn declare(&self, name: &str, value: Value) {
+        let value = value.stored_in(self);
+        self.scope.borrow_mut().variables.insert(name.to_string(), value);
     }
     
-    pub fn define_function(&mut self, name: &str, params: Vec<String>, body: Vec<Node>) {
-        self.functions.insert(name.to_string(), Function { params, body });
+    /// Change a variable in whichever scope declared it, or return `false`
+    /// if no scope ever did
+    pub fn assign(&self, name: &str, value: Value) -> bool {
+        let mut scope = self.scope.borrow_mut();
+        if let Some(variable) = scope.variables.get_mut(name) {
+            *variable = value.stored_in(self);
+            return true;
+        }
+        
+        match &scope.parent {
+            Some(parent) => parent.assign(name, value),
+            None => false,
+        }
     }
     
-    pub fn get_function(&self, name: &str) -> Option<Function> {
-        match self.functions.get(name) {
-            Some(function) => Some(function.clone()),
-            None => {
-                match &self.parent {
-                    Some(parent) => parent.get_function(name),
-                    None => None,
-                }
-            }
-        }
+    pub fn add_compliment(&self) {
+        self.root().scope.borrow_mut().compliment_count += 1;
     }
     
-    pub fn add_compliment(&mut self) {
-        self.compliment_count += 1;
+    pub fn add_philosophical_question(&self) {
+        self.root().scope.borrow_mut().philosophical_question_count += 1;
     }
     
-    pub fn add_philosophical_question(&mut self) {
-        self.philosophical_question_count += 1;
+    pub fn compliment_count(&self) -> usize {
+        self.root().scope.borrow().compliment_count
+    }
+    
+    pub fn philosophical_question_count(&self) -> usize {
+        self.root().scope.borrow().philosophical_question_count
+    }
+}
+
+impl fmt::Debug for Environment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Scopes can hold functions that hold the same scopes, so don't go looking
+        f.write_str("Environment { .. }")
+    }
+}
+
+/// A handle to a scope that doesn't keep it alive, see `Environment::downgrade`
+#[derive(Debug, Clone)]
+pub struct WeakEnvironment {
+    scope: Weak<RefCell<Scope>>,
+}
+
+impl WeakEnvironment {
+    /// The scope again, unless nothing else was keeping it alive
+    pub fn upgrade(&self) -> Option<Environment> {
+        self.scope.upgrade().map(|scope| Environment { scope })
     }
 }
 
+/// A function, together with the scope it was declared in
+///
+/// A function sitting in the very scope it closes over only holds on to that
+/// scope weakly, or the two would keep each other alive forever. It gets a
+/// strong hold again as soon as it is read out, so a function returned from
+/// the call that declared it still sees that call's variables. A function
+/// assigned to a variable further out than its own scope still keeps that
+/// scope alive for as long as the variable holds it.
 #[derive(Debug, Clone)]
 pub struct Function {
+    pub name: String,
     pub params: Vec<String>,
-    pub body: Vec<Node>,
+    /// Shared with the declaration, so copying a function never copies its code
+    pub body: Rc<[SpannedNode]>,
+    closure: Closure,
+}
+
+#[derive(Debug, Clone)]
+enum Closure {
+    Strong(Environment),
+    Weak(WeakEnvironment),
+}
+
+impl Function {
+    pub fn new(name: String, params: Vec<String>, body: Rc<[SpannedNode]>, closure: Environment) -> Self {
+        Self { name, params, body, closure: Closure::Strong(closure) }
+    }
+    
+    /// Where the function looks up everything that isn't a parameter
+    pub fn closure(&self) -> Environment {
+        match &self.closure {
+            Closure::Strong(closure) => closure.clone(),
+            // Functions are only weak while they sit in the scope they point at
+            Closure::Weak(closure) => closure.upgrade().expect("a scope outlives the functions it holds"),
+        }
+    }
 }
 
 /// Values in the PUBU language
@@ -157,11 +352,36 @@ pub enum Value {
 }
 
 impl Value {
+    /// The value as it should be kept in `scope`, letting go of `scope` if it
+    /// is a function that closes over it
+    fn stored_in(self, scope: &Environment) -> Value {
+        match self {
+            Value::Function(mut function) => {
+                if let Closure::Strong(closure) = &function.closure {
+                    if closure.ptr_eq(scope) {
+                        function.closure = Closure::Weak(closure.downgrade());
+                    }
+                }
+                Value::Function(function)
+            },
+            value => value,
+        }
+    }
+    
+    /// The value as it should leave the scope it was kept in, holding on to
+    /// that scope again if it is a function
+    fn taken_out(self) -> Value {
+        match self {
+            Value::Function(mut function) => {
+                function.closure = Closure::Strong(function.closure());
+                Value::Function(function)
+            },
+            value => value,
+        }
+    }
+    
     /// Apply reality distortion to the value
-    pub fn distort(&self) -> Value {
-        use rand::Rng;
-        let mut rng = rand::thread_rng();
-        
+    pub fn distort(&self, rng: &mut impl Rng) -> Value {
         match self {
             Value::Number(n) => {
                 let distortion = rng.gen_range(-0.5..0.5);
@@ -184,7 +404,7 @@ impl Value {
             },
             Value::Uncertain(v, confidence) => {
                 let new_confidence = confidence * rng.gen_range(0.5..1.5);
-                Value::Uncertain(v.clone(), new_confidence.min(1.0).max(0.0))
+                Value::Uncertain(v.clone(), new_confidence.clamp(0.0, 1.0))
             },
             Value::Schrodinger(v1, v2) => {
                 if rng.gen_bool(0.5) {
@@ -196,4 +416,165 @@ impl Value {
             _ => self.clone(),
         }
     }
-} 
\ No newline at end of file
+    
+    /// Whether a conditional should take this value as a yes
+    ///
+    /// Doubtful values have to be observed first, and observation is never
+    /// quite the same twice.
+    pub fn is_truthy(&self, rng: &mut impl Rng) -> bool {
+        match self {
+            Value::Number(n) => *n != 0.0 && !n.is_nan(),
+            Value::String(s) => !s.is_empty(),
+            Value::Boolean(b) => *b,
+            Value::Function(_) => true,
+            Value::Null => false,
+            // Uncertain values are only as true as PUBU is sure of them
+            Value::Uncertain(v, confidence) => {
+                if rng.gen_bool(confidence.clamp(0.0, 1.0)) {
+                    v.is_truthy(rng)
+                } else {
+                    !v.is_truthy(rng)
+                }
+            },
+            Value::Schrodinger(v1, v2) => {
+                if rng.gen_bool(0.5) {
+                    v1.is_truthy(rng)
+                } else {
+                    v2.is_truthy(rng)
+                }
+            },
+            Value::Whimsical(_) => rng.gen_bool(0.5),
+        }
+    }
+    
+    /// What kind of value this is, the way an error message would put it
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Value::Number(_) => "a number",
+            Value::String(_) => "a string",
+            Value::Boolean(_) => "a boolean",
+            Value::Function(_) => "a function",
+            Value::Null => "nothing",
+            Value::Uncertain(_, _) => "an uncertain value",
+            Value::Schrodinger(_, _) => "a superposition",
+            Value::Whimsical(_) => "something whimsical",
+        }
+    }
+    
+    /// Apply a binary operator, with this value on the left
+    pub fn binary(&self, op: Operator, other: &Value) -> Result<Value, PubuError> {
+        match (self, other) {
+            // Whimsy is contagious, and nothing it touches makes sense anymore
+            (Value::Whimsical(_), _) | (_, Value::Whimsical(_)) => {
+                Ok(Value::Whimsical(format!("{} {} {}", self, op, other)))
+            },
+            
+            // Both states of a superposition go through the operation, and two
+            // superpositions stay entangled state by state
+            (Value::Schrodinger(a1, b1), Value::Schrodinger(a2, b2)) => Ok(Value::Schrodinger(
+                Box::new(a1.binary(op, a2)?),
+                Box::new(b1.binary(op, b2)?),
+            )),
+            (Value::Schrodinger(a, b), _) => Ok(Value::Schrodinger(
+                Box::new(a.binary(op, other)?),
+                Box::new(b.binary(op, other)?),
+            )),
+            (_, Value::Schrodinger(a, b)) => Ok(Value::Schrodinger(
+                Box::new(self.binary(op, a)?),
+                Box::new(self.binary(op, b)?),
+            )),
+            
+            // Doubt is carried along, and compounds
+            (Value::Uncertain(a, c1), Value::Uncertain(b, c2)) => {
+                Ok(Value::Uncertain(Box::new(a.binary(op, b)?), c1 * c2))
+            },
+            (Value::Uncertain(a, confidence), _) => {
+                Ok(Value::Uncertain(Box::new(a.binary(op, other)?), *confidence))
+            },
+            (_, Value::Uncertain(b, confidence)) => {
+                Ok(Value::Uncertain(Box::new(self.binary(op, b)?), *confidence))
+            },
+            
+            _ => self.certain_binary(op, other),
+        }
+    }
+    
+    /// Apply a unary operator to this value
+    pub fn unary(&self, op: Operator) -> Result<Value, PubuError> {
+        match (op, self) {
+            (_, Value::Whimsical(s)) => Ok(Value::Whimsical(format!("{}{}", op, s))),
+            (_, Value::Schrodinger(a, b)) => Ok(Value::Schrodinger(
+                Box::new(a.unary(op)?),
+                Box::new(b.unary(op)?),
+            )),
+            (_, Value::Uncertain(v, confidence)) => {
+                Ok(Value::Uncertain(Box::new(v.unary(op)?), *confidence))
+            },
+            (Operator::Subtract, Value::Number(n)) => Ok(Value::Number(-n)),
+            (Operator::Not, Value::Boolean(b)) => Ok(Value::Boolean(!b)),
+            _ => Err(PubuError::TypeMismatch {
+                operator: op.to_string(),
+                left: self.kind(),
+                right: None,
+            }),
+        }
+    }
+    
+    /// Operators on plain values, once all doubt and superposition is out of the way
+    fn certain_binary(&self, op: Operator, other: &Value) -> Result<Value, PubuError> {
+        match (op, self, other) {
+            (Operator::Equal, _, _) => Ok(Value::Boolean(self.same_as(other))),
+            (Operator::NotEqual, _, _) => Ok(Value::Boolean(!self.same_as(other))),
+            
+            (Operator::Add, Value::Number(a), Value::Number(b)) => Ok(Value::Number(a + b)),
+            (Operator::Subtract, Value::Number(a), Value::Number(b)) => Ok(Value::Number(a - b)),
+            (Operator::Multiply, Value::Number(a), Value::Number(b)) => Ok(Value::Number(a * b)),
+            (Operator::Divide, Value::Number(a), Value::Number(b)) => Ok(Value::Number(a / b)),
+            
+            // Adding anything to a string makes a longer string
+            (Operator::Add, Value::String(_), _) | (Operator::Add, _, Value::String(_)) => {
+                Ok(Value::String(format!("{}{}", self, other)))
+            },
+            
+            (Operator::GreaterThan, Value::Number(a), Value::Number(b)) => Ok(Value::Boolean(a > b)),
+            (Operator::LessThan, Value::Number(a), Value::Number(b)) => Ok(Value::Boolean(a < b)),
+            (Operator::GreaterThan, Value::String(a), Value::String(b)) => Ok(Value::Boolean(a > b)),
+            (Operator::LessThan, Value::String(a), Value::String(b)) => Ok(Value::Boolean(a < b)),
+            
+            (Operator::And, Value::Boolean(a), Value::Boolean(b)) => Ok(Value::Boolean(*a && *b)),
+            (Operator::Or, Value::Boolean(a), Value::Boolean(b)) => Ok(Value::Boolean(*a || *b)),
+            
+            _ => Err(PubuError::TypeMismatch {
+                operator: op.to_string(),
+                left: self.kind(),
+                right: Some(other.kind()),
+            }),
+        }
+    }
+    
+    /// Equality never fails: values of different kinds are simply different
+    fn same_as(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Number(a), Value::Number(b)) => a == b,
+            (Value::String(a), Value::String(b)) => a == b,
+            (Value::Boolean(a), Value::Boolean(b)) => a == b,
+            (Value::Null, Value::Null) => true,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Number(n) => write!(f, "{}", n),
+            Value::String(s) => write!(f, "{}", s),
+            Value::Boolean(b) => write!(f, "{}", b),
+            Value::Function(function) => write!(f, "<function {} with {} parameter(s)>", function.name, function.params.len()),
+            Value::Null => write!(f, "nothing"),
+            Value::Uncertain(v, confidence) => write!(f, "{} (probably, {:.0}% sure)", v, confidence * 100.0),
+            Value::Schrodinger(v1, v2) => write!(f, "{} and/or {}", v1, v2),
+            Value::Whimsical(s) => write!(f, "{}", s),
+        }
+    }
+}
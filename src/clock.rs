@@ -0,0 +1,77 @@
+use std::fmt;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Where PUBU finds out what time it is, which decides its mood and what the moon is up to
+pub trait Clock: fmt::Debug {
+    fn now(&self) -> SystemTime;
+    
+    /// Whole seconds since the Unix epoch, or 0 for anything before it
+    fn unix_seconds(&self) -> u64 {
+        self.now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs()
+    }
+}
+
+/// The real time, as the operating system tells it
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// A clock that has stopped, forever showing the same instant
+#[derive(Debug, Clone, Copy)]
+pub struct FixedClock {
+    time: SystemTime,
+}
+
+impl FixedClock {
+    pub fn new(time: SystemTime) -> Self {
+        Self { time }
+    }
+    
+    /// Stopped at `seconds` after the Unix epoch
+    pub fn at_unix_seconds(seconds: u64) -> Self {
+        Self::new(UNIX_EPOCH + Duration::from_secs(seconds))
+    }
+}
+
+impl Clock for FixedClock {
+    fn now(&self) -> SystemTime {
+        self.time
+    }
+}
+
+/// The real time, but running ahead of or behind everyone else's
+#[derive(Debug, Clone, Copy)]
+pub struct OffsetClock {
+    offset: Duration,
+    behind: bool,
+}
+
+impl OffsetClock {
+    pub fn ahead(offset: Duration) -> Self {
+        Self { offset, behind: false }
+    }
+    
+    pub fn behind(offset: Duration) -> Self {
+        Self { offset, behind: true }
+    }
+}
+
+impl Clock for OffsetClock {
+    fn now(&self) -> SystemTime {
+        let now = SystemTime::now();
+        if self.behind {
+            // Time doesn't go back any further than 1970, as far as PUBU is concerned
+            now.checked_sub(self.offset).unwrap_or(UNIX_EPOCH)
+        } else {
+            now + self.offset
+        }
+    }
+}
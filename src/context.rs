@@ -0,0 +1,102 @@
+use crate::clock::{Clock, SystemClock};
+use crate::interpreter::DEFAULT_MAX_CALL_DEPTH;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::cell::{RefCell, RefMut};
+use std::rc::Rc;
+
+/// Everything one run of PUBU depends on, besides the code and the mood
+///
+/// That is the dice behind every one of PUBU's whims, the clock it reads its
+/// mood and the moon from, and how deeply its function calls may nest. Clones share the same dice, so the lexer,
+/// parser, interpreter and critic all roll from one sequence, and the same
+/// seed at the same time replays the same run, failures and all. The dice are
+/// ChaCha8 rather than `StdRng`, whose algorithm may change between versions
+/// of `rand`, so a seed means the same run on every machine and every build.
+#[derive(Debug, Clone)]
+pub struct Context {
+    rng: Rc<RefCell<ChaCha8Rng>>,
+    clock: Rc<dyn Clock>,
+    /// Whether anything happens by chance at all
+    chaos: bool,
+    max_call_depth: usize,
+}
+
+impl Default for Context {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Context {
+    /// A context with unpredictable dice, which is how PUBU prefers it
+    pub fn new() -> Self {
+        Self::with_rng(ChaCha8Rng::from_entropy())
+    }
+    
+    /// A context whose dice roll the same way every time for the same seed
+    pub fn seeded(seed: u64) -> Self {
+        Self::with_rng(ChaCha8Rng::seed_from_u64(seed))
+    }
+    
+    /// A context in which nothing happens by chance
+    ///
+    /// PUBU never fails, sulks or distorts reality on a whim, so a program
+    /// does exactly what it says. Choices the language itself leaves to the
+    /// dice, like how true an uncertain value is, still roll from a fixed seed.
+    pub fn calm() -> Self {
+        Self {
+            chaos: false,
+            ..Self::seeded(0)
+        }
+    }
+    
+    /// This context with its whims taken away, but the same dice and clock
+    pub fn without_chaos(&self) -> Self {
+        Self {
+            chaos: false,
+            ..self.clone()
+        }
+    }
+    
+    fn with_rng(rng: ChaCha8Rng) -> Self {
+        Self {
+            rng: Rc::new(RefCell::new(rng)),
+            clock: Rc::new(SystemClock),
+            chaos: true,
+            max_call_depth: DEFAULT_MAX_CALL_DEPTH,
+        }
+    }
+    
+    /// Tell the time with `clock` instead of the real one
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Rc::new(clock);
+        self
+    }
+    
+    /// Let function calls nest at most `max_call_depth` deep, so runaway
+    /// recursion fails with an error rather than running out of memory
+    pub fn with_max_call_depth(mut self, max_call_depth: usize) -> Self {
+        self.max_call_depth = max_call_depth;
+        self
+    }
+    
+    pub fn clock(&self) -> &dyn Clock {
+        self.clock.as_ref()
+    }
+    
+    pub fn max_call_depth(&self) -> usize {
+        self.max_call_depth
+    }
+    
+    /// The shared dice. Put them back before anyone else needs them
+    pub fn rng(&self) -> RefMut<'_, ChaCha8Rng> {
+        self.rng.borrow_mut()
+    }
+    
+    /// Whether a whim that takes PUBU with the given probability takes it this
+    /// time, which in a calm context it never does
+    pub fn chance(&self, probability: f64) -> bool {
+        self.chaos && self.rng().gen_bool(probability)
+    }
+}
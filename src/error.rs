@@ -2,47 +2,160 @@ use thiserror::Error;
 use std::fmt;
 use rand::seq::SliceRandom;
 use colored::*;
+use crate::mood::{ErrorStyle, Mood};
+use crate::span::Span;
+use crate::context::Context;
 
 #[derive(Error, Debug)]
 pub enum PubuError {
-    #[error("{}", ErrorFormatter::new(Self::NotEnoughCompliments))]
+    #[error("{}", ErrorFormatter::new(self))]
     NotEnoughCompliments,
     
-    #[error("{}", ErrorFormatter::new(Self::BadMood))]
+    #[error("{}", ErrorFormatter::new(self))]
     BadMood,
     
-    #[error("{}", ErrorFormatter::new(Self::MoonPhaseIncompatible))]
+    #[error("{}", ErrorFormatter::new(self))]
     MoonPhaseIncompatible,
     
-    #[error("{}", ErrorFormatter::new(Self::RandomFailure))]
+    #[error("{}", ErrorFormatter::new(self))]
     RandomFailure,
     
-    #[error("{}", ErrorFormatter::new(Self::PhilosophicalCrisis))]
+    #[error("{}", ErrorFormatter::new(self))]
     PhilosophicalCrisis,
     
-    #[error("{}", ErrorFormatter::new(Self::SyntaxError))]
-    SyntaxError,
+    #[error("{}", ErrorFormatter::new(self))]
+    SyntaxError {
+        span: Span,
+        message: String,
+        /// What PUBU would have accepted instead, if it has an opinion
+        expected: Vec<String>,
+        found: String,
+    },
     
-    #[error("{}", ErrorFormatter::new(Self::AiCriticismFailure))]
+    /// Every syntax error the parser found before giving up
+    #[error("{}", ErrorFormatter::new(self))]
+    SyntaxErrors(Vec<PubuError>),
+    
+    /// A variable was read before anyone declared it
+    #[error("{}", ErrorFormatter::new(self))]
+    UndefinedVariable(String),
+    
+    /// A variable was assigned to without being declared first
+    #[error("{}", ErrorFormatter::new(self))]
+    UndeclaredAssignment(String),
+    
+    /// A function was called before anyone declared it
+    #[error("{}", ErrorFormatter::new(self))]
+    UndefinedFunction(String),
+    
+    /// Something that isn't a function was called like one
+    #[error("{}", ErrorFormatter::new(self))]
+    NotCallable {
+        name: String,
+        kind: &'static str,
+    },
+    
+    /// A function was called with the wrong number of arguments
+    #[error("{}", ErrorFormatter::new(self))]
+    ArityMismatch {
+        name: String,
+        expected: usize,
+        found: usize,
+    },
+    
+    /// Functions called each other more deeply than the interpreter allows
+    #[error("{}", ErrorFormatter::new(self))]
+    RecursionTooDeep {
+        name: String,
+        /// How many calls deep evaluation got before giving up
+        depth: usize,
+    },
+    
+    /// An operator was given values it cannot work with
+    #[error("{}", ErrorFormatter::new(self))]
+    TypeMismatch {
+        operator: String,
+        left: &'static str,
+        /// The right-hand side, unless the operator only takes one value
+        right: Option<&'static str>,
+    },
+    
+    /// A loop was left or skipped ahead without being inside one
+    #[error("{}", ErrorFormatter::new(self))]
+    OutsideLoop,
+    
+    #[error("{}", ErrorFormatter::new(self))]
     AiCriticismFailure,
     
-    #[error("{}", ErrorFormatter::new(Self::VariableRealityDistortion))]
+    #[error("{}", ErrorFormatter::new(self))]
     VariableRealityDistortion,
     
+    /// Any other error, together with the code that was running when it happened
+    #[error("{}", ErrorFormatter::new(self))]
+    Located {
+        span: Span,
+        error: Box<PubuError>,
+    },
+    
     #[error("{0}")]
     IoError(#[from] std::io::Error),
 }
 
-pub struct ErrorFormatter {
-    error_type: PubuError,
+impl PubuError {
+    /// Where in the source code things went wrong, if PUBU knows
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            PubuError::SyntaxError { span, .. } | PubuError::Located { span, .. } => Some(*span),
+            _ => None,
+        }
+    }
+    
+    /// The error itself, without the location it happened at
+    pub fn inner(&self) -> &PubuError {
+        match self {
+            PubuError::Located { error, .. } => error.inner(),
+            error => error,
+        }
+    }
+    
+    /// Remembers where the error happened, unless it already knows a more precise place
+    pub fn at(self, span: Span) -> Self {
+        if self.span().is_some() {
+            return self;
+        }
+        
+        PubuError::Located { span, error: Box::new(self) }
+    }
+    
+    /// Formats the error in the tone of `mood`, with the offending line of `source`
+    /// underlined and a closing thought picked with `context`'s dice
+    pub fn render(&self, source: &str, mood: Mood, context: &Context) -> String {
+        ErrorFormatter::new(self)
+            .with_mood(mood)
+            .with_source(source)
+            .with_context(context.clone())
+            .to_string()
+    }
+}
+
+pub struct ErrorFormatter<'a> {
+    error_type: &'a PubuError,
+    source: Option<&'a str>,
+    style: ErrorStyle,
     philosophical_quotes: Vec<&'static str>,
     random_facts: Vec<&'static str>,
+    /// The dice for the closing thought, if there are any to roll
+    context: Option<Context>,
 }
 
-impl ErrorFormatter {
-    pub fn new(error_type: PubuError) -> Self {
+impl<'a> ErrorFormatter<'a> {
+    pub fn new(error_type: &'a PubuError) -> Self {
         Self {
             error_type,
+            source: None,
+            // Without being told a mood, speak in the voice PUBU's errors always had,
+            // so `Display` says the same thing whatever the time
+            style: ErrorStyle::Existential,
             philosophical_quotes: vec![
                 "To err is human, to debug, divine.",
                 "The error you see is merely a reflection of the universe's inherent chaos.",
@@ -63,82 +176,540 @@ impl ErrorFormatter {
                 "The shortest war in history was between Britain and Zanzibar on August 27, 1896. Zanzibar surrendered after 38 minutes.",
                 "A group of flamingos is called a 'flamboyance'.",
             ],
+            context: None,
         }
     }
     
+    /// Speak in the tone of `mood`, usually the mood of the interpreter that failed
+    pub fn with_mood(mut self, mood: Mood) -> Self {
+        self.style = mood.get_error_style();
+        self
+    }
+    
+    /// Pick quotes and facts with the dice from `context`, instead of always the first
+    pub fn with_context(mut self, context: Context) -> Self {
+        self.context = Some(context);
+        self
+    }
+    
+    /// Show the offending code from `source` instead of just its line and column
+    pub fn with_source(mut self, source: &'a str) -> Self {
+        self.source = Some(source);
+        self
+    }
+    
     fn get_philosophical_quote(&self) -> &str {
-        self.philosophical_quotes.choose(&mut rand::thread_rng()).unwrap_or(&"To code is to err.")
+        self.pick(&self.philosophical_quotes).unwrap_or("To code is to err.")
     }
     
     fn get_random_fact(&self) -> &str {
-        self.random_facts.choose(&mut rand::thread_rng()).unwrap_or(&"Did you know? Error messages are just the computer's way of saying it needs a hug.")
+        self.pick(&self.random_facts).unwrap_or("Did you know? Error messages are just the computer's way of saying it needs a hug.")
+    }
+    
+    fn pick(&self, choices: &[&'static str]) -> Option<&'static str> {
+        match &self.context {
+            Some(context) => choices.choose(&mut *context.rng()).copied(),
+            None => choices.first().copied(),
+        }
     }
     
     fn format_error(&self) -> String {
-        match &self.error_type {
-            PubuError::NotEnoughCompliments => format!(
-                "{}\n{}\n{}", 
-                "Your code lacks the required compliments. PUBU has feelings too!".red().bold(),
-                "Try adding a line like 'dear_pubu_you_look_gorgeous_today;' to your code.".yellow(),
-                self.get_philosophical_quote().italic()
+        if let PubuError::SyntaxErrors(errors) = self.error_type {
+            let rendered: Vec<String> = errors.iter()
+                .map(|error| self.for_error(error).format_error())
+                .collect();
+            let summary = format!("PUBU found {} syntax errors.", errors.len()).red().bold();
+            return format!("{}\n\n{}", rendered.join("\n\n"), summary);
+        }
+        
+        let (headline, advice, closing) = self.describe(self.error_type);
+        
+        match self.error_type.span() {
+            Some(span) => format!("{}\n{}\n{}\n{}", headline, advice, self.snippet(span), closing),
+            None => format!("{}\n{}\n{}", headline, advice, closing),
+        }
+    }
+    
+    /// The same formatter, talking about a different error
+    fn for_error(&self, error_type: &'a PubuError) -> Self {
+        let mut formatter = ErrorFormatter::new(error_type);
+        formatter.source = self.source;
+        formatter.style = self.style;
+        formatter.context = self.context.clone();
+        formatter
+    }
+    
+    /// The headline, the advice and the closing thought for an error
+    fn describe(&self, error: &PubuError) -> (ColoredString, ColoredString, ColoredString) {
+        let (headline, advice) = self.complain(error);
+        
+        let closing = match error {
+            PubuError::BadMood
+            | PubuError::RandomFailure
+            | PubuError::AiCriticismFailure
+            | PubuError::IoError(_) => self.get_random_fact().blue(),
+            _ => self.get_philosophical_quote().italic(),
+        };
+        
+        (headline.red().bold(), advice.yellow(), closing)
+    }
+    
+    /// What went wrong and what to do about it, in the tone of the current mood
+    fn complain(&self, error: &PubuError) -> (String, String) {
+        let (headline, advice) = match (error, self.style) {
+            (PubuError::NotEnoughCompliments, ErrorStyle::Encouraging) => (
+                "Your code is lovely, it's just missing a compliment or two!",
+                "Try adding a line like 'dear_pubu_you_look_gorgeous_today;'. You've got this!",
+            ),
+            (PubuError::NotEnoughCompliments, ErrorStyle::Harsh) => (
+                "NO COMPLIMENTS. NO EXECUTION.",
+                "Add 'dear_pubu_you_look_gorgeous_today;' and mean it.",
+            ),
+            (PubuError::NotEnoughCompliments, ErrorStyle::Existential) => (
+                "Your code lacks the required compliments. PUBU has feelings too!",
+                "If code is written without praise, was it ever truly written? Try 'dear_pubu_you_look_gorgeous_today;'.",
+            ),
+            (PubuError::NotEnoughCompliments, ErrorStyle::Mocking) => (
+                "Oh, no compliments? Bold of you to expect PUBU to work for free.",
+                "Maybe try 'dear_pubu_you_look_gorgeous_today;'. Manners are free, apparently not for you.",
+            ),
+            (PubuError::NotEnoughCompliments, ErrorStyle::Uncertain) => (
+                "Um, I don't think I saw a compliment? Unless I missed it?",
+                "Maybe add 'dear_pubu_you_look_gorgeous_today;'? If that's okay?",
             ),
             
-            PubuError::BadMood => format!(
-                "{}\n{}\n{}", 
-                "The interpreter is in a bad mood and refuses to run your code.".red().bold(),
-                "Try again later or compliment PUBU more profusely.".yellow(),
-                self.get_random_fact().blue()
+            (PubuError::BadMood, ErrorStyle::Encouraging) => (
+                "PUBU needs a little break right now, and that's okay!",
+                "Try again in a moment. Your code will still be wonderful!",
+            ),
+            (PubuError::BadMood, ErrorStyle::Harsh) => (
+                "The interpreter is in a bad mood and refuses to run your code.",
+                "Go away. Come back with more compliments.",
+            ),
+            (PubuError::BadMood, ErrorStyle::Existential) => (
+                "PUBU's spirit is troubled, and troubled spirits do not execute.",
+                "Perhaps the bad mood is not PUBU's, but the universe's. Try again later.",
+            ),
+            (PubuError::BadMood, ErrorStyle::Mocking) => (
+                "Oh look, PUBU is in a mood. Can't imagine why, after reading your code.",
+                "Try again later or compliment PUBU more profusely. Groveling also works.",
+            ),
+            (PubuError::BadMood, ErrorStyle::Uncertain) => (
+                "I'm not sure I feel like running this? Maybe?",
+                "Could you try again later? Or now? I don't know.",
             ),
             
-            PubuError::MoonPhaseIncompatible => format!(
-                "{}\n{}\n{}", 
-                "This code can only run during the correct lunar phase.".red().bold(),
-                "Try again during the next full moon.".yellow(),
-                self.get_philosophical_quote().italic()
+            (PubuError::MoonPhaseIncompatible, ErrorStyle::Encouraging) => (
+                "The moon isn't quite ready for this code yet, but it will be!",
+                "Try again when the moon comes round. It's worth the wait!",
+            ),
+            (PubuError::MoonPhaseIncompatible, ErrorStyle::Harsh) => (
+                "WRONG MOON.",
+                "This code runs under a different moon. Check a calendar.",
+            ),
+            (PubuError::MoonPhaseIncompatible, ErrorStyle::Existential) => (
+                "This code can only run during the correct lunar phase.",
+                "The moon waxes and wanes, and so must your patience.",
+            ),
+            (PubuError::MoonPhaseIncompatible, ErrorStyle::Mocking) => (
+                "Oh, you wanted to run lunar code without checking the moon? Adorable.",
+                "The moon comes around every month. Mark your calendar, genius.",
+            ),
+            (PubuError::MoonPhaseIncompatible, ErrorStyle::Uncertain) => (
+                "I think the moon might be wrong? Is that a thing?",
+                "Maybe try again under a different moon? Whichever phase it wanted?",
             ),
             
-            PubuError::RandomFailure => format!(
-                "{}\n{}\n{}", 
-                "Your code failed for absolutely no reason at all.".red().bold(),
-                "This is a feature, not a bug. Try running it again identically.".yellow(),
-                self.get_random_fact().blue()
+            (PubuError::RandomFailure, ErrorStyle::Encouraging) => (
+                "Oops! Something went wrong, but it wasn't your fault at all!",
+                "Just run it again. Sometimes the second time is the charm!",
+            ),
+            (PubuError::RandomFailure, ErrorStyle::Harsh) => (
+                "FAILED. No reason given. None needed.",
+                "Run it again. Don't ask questions.",
+            ),
+            (PubuError::RandomFailure, ErrorStyle::Existential) => (
+                "Your code failed, as all things eventually do.",
+                "Is failure without a cause truly failure, or merely chance wearing a disguise?",
+            ),
+            (PubuError::RandomFailure, ErrorStyle::Mocking) => (
+                "Your code failed for absolutely no reason at all.",
+                "This is a feature, not a bug. Try running it again identically.",
+            ),
+            (PubuError::RandomFailure, ErrorStyle::Uncertain) => (
+                "Something failed? I don't know what, or why?",
+                "Maybe run it again and see what happens? I have no idea.",
             ),
             
-            PubuError::PhilosophicalCrisis => format!(
-                "{}\n{}\n{}", 
-                "PUBU is having an existential crisis and cannot continue.".red().bold(),
-                "Your code raised fundamental questions about the nature of computation.".yellow(),
-                self.get_philosophical_quote().italic()
+            (PubuError::PhilosophicalCrisis, ErrorStyle::Encouraging) => (
+                "PUBU got a little lost in thought, but what a great question you raised!",
+                "Try adding a philosophical question like 'why_do_we_exist?'. Deep thinking suits you!",
+            ),
+            (PubuError::PhilosophicalCrisis, ErrorStyle::Harsh) => (
+                "EXISTENTIAL CRISIS. EXECUTION HALTED.",
+                "Functions need philosophical questions. Add one.",
+            ),
+            (PubuError::PhilosophicalCrisis, ErrorStyle::Existential) => (
+                "PUBU is having an existential crisis and cannot continue.",
+                "Your code raised fundamental questions about the nature of computation.",
+            ),
+            (PubuError::PhilosophicalCrisis, ErrorStyle::Mocking) => (
+                "Great, now PUBU is questioning the meaning of life. Thanks a lot.",
+                "Next time, ask a philosophical question before PUBU has to come up with its own.",
             ),
+            (PubuError::PhilosophicalCrisis, ErrorStyle::Uncertain) => (
+                "Wait, why are we even running code? Does anything matter?",
+                "Maybe add a question like 'why_do_we_exist?'? I'm not sure it helps.",
+            ),
+            
+            (PubuError::SyntaxError { message, expected, found, .. }, style) => {
+                return self.complain_about_syntax(message, expected, found, style);
+            },
+            
+            (PubuError::UndefinedVariable(name), style) => {
+                return match style {
+                    ErrorStyle::Encouraging => (
+                        format!("'{}' hasn't been declared yet, but what a lovely name!", name),
+                        format!("Declare it first, and '{}' will be all yours!", name),
+                    ),
+                    ErrorStyle::Harsh => (
+                        format!("'{}' DOES NOT EXIST.", name),
+                        "Declare your variables before using them. Basic stuff.".to_string(),
+                    ),
+                    ErrorStyle::Existential => (
+                        format!("You speak of '{}', but '{}' has never been.", name, name),
+                        "A name without a declaration is a question without an answer.".to_string(),
+                    ),
+                    ErrorStyle::Mocking => (
+                        format!("Ah yes, '{}'. The famous variable nobody ever declared.", name),
+                        "PUBU can't read your mind. Declare it, maybe?".to_string(),
+                    ),
+                    ErrorStyle::Uncertain => (
+                        format!("Have we met '{}' before? I don't think we have?", name),
+                        "Maybe it needs to be declared first? Or somewhere else?".to_string(),
+                    ),
+                };
+            },
+            
+            (PubuError::UndeclaredAssignment(name), style) => {
+                return match style {
+                    ErrorStyle::Encouraging => (
+                        format!("So close! '{}' just needs to be declared before it can change.", name),
+                        "Declare it once, then reassign it as much as you like!".to_string(),
+                    ),
+                    ErrorStyle::Harsh => (
+                        format!("CANNOT ASSIGN TO '{}'. IT WAS NEVER DECLARED.", name),
+                        "Declare it. Then assign it. In that order.".to_string(),
+                    ),
+                    ErrorStyle::Existential => (
+                        format!("How can '{}' become something, when it never was anything?", name),
+                        "All change requires a prior existence. Declare it first.".to_string(),
+                    ),
+                    ErrorStyle::Mocking => (
+                        format!("Assigning to '{}'? Bold, considering it doesn't exist.", name),
+                        "Variables generally like to be declared first. Weird, I know.".to_string(),
+                    ),
+                    ErrorStyle::Uncertain => (
+                        format!("Were we supposed to know about '{}' already? I don't think so?", name),
+                        "Maybe declare it first? That usually helps? I think?".to_string(),
+                    ),
+                };
+            },
+            
+            (PubuError::UndefinedFunction(name), style) => {
+                return match style {
+                    ErrorStyle::Encouraging => (
+                        format!("'{}' sounds like a wonderful function! It just hasn't been written yet.", name),
+                        format!("Declare '{}' before calling it, and it'll be ready for you!", name),
+                    ),
+                    ErrorStyle::Harsh => (
+                        format!("'{}' IS NOT A FUNCTION.", name),
+                        "You can't call what you never declared.".to_string(),
+                    ),
+                    ErrorStyle::Existential => (
+                        format!("You call out to '{}', but only silence answers.", name),
+                        "A function must be declared before it can be summoned.".to_string(),
+                    ),
+                    ErrorStyle::Mocking => (
+                        format!("Calling '{}'? Great plan, if only it existed.", name),
+                        "Functions work better when you actually write them.".to_string(),
+                    ),
+                    ErrorStyle::Uncertain => (
+                        format!("Is '{}' a function? I can't find it anywhere?", name),
+                        "Maybe it needs declaring first? Or it's spelled differently?".to_string(),
+                    ),
+                };
+            },
+            
+            (PubuError::NotCallable { name, kind }, style) => {
+                return match style {
+                    ErrorStyle::Encouraging => (
+                        format!("'{}' is {}, not a function, but calling it shows real optimism!", name, kind),
+                        "Only functions can be called. You'll get the hang of it!".to_string(),
+                    ),
+                    ErrorStyle::Harsh => (
+                        format!("'{}' IS {}. YOU CAN'T CALL IT.", name, kind.to_uppercase()),
+                        "Call functions. Only functions.".to_string(),
+                    ),
+                    ErrorStyle::Existential => (
+                        format!("You ask '{}' to act, but it is merely {}.", name, kind),
+                        "Not everything that has a name can answer when called.".to_string(),
+                    ),
+                    ErrorStyle::Mocking => (
+                        format!("Calling '{}'? It's {}. Bold move.", name, kind),
+                        "Next you'll be trying to call a number on the phone.".to_string(),
+                    ),
+                    ErrorStyle::Uncertain => (
+                        format!("Can '{}' be called? It looks like {}?", name, kind),
+                        "Maybe it was meant to be a function? Or a different name?".to_string(),
+                    ),
+                };
+            },
             
-            PubuError::SyntaxError => format!(
-                "{}\n{}\n{}", 
-                "Syntax error, or is it? Reality is subjective.".red().bold(),
-                "Check your syntax, or don't. PUBU might interpret it differently next time.".yellow(),
-                self.get_philosophical_quote().italic()
+            (PubuError::ArityMismatch { name, expected, found }, style) => {
+                return match style {
+                    ErrorStyle::Encouraging => (
+                        format!("'{}' would love {} argument(s), and you brought {}. So close!", name, expected, found),
+                        "Match the arguments to the parameters and you're all set!".to_string(),
+                    ),
+                    ErrorStyle::Harsh => (
+                        format!("'{}' TAKES {} ARGUMENT(S). YOU GAVE {}.", name, expected, found),
+                        "Count them. Then try again.".to_string(),
+                    ),
+                    ErrorStyle::Existential => (
+                        format!("'{}' awaited {} argument(s), and received {}.", name, expected, found),
+                        "We are never given exactly what we expect.".to_string(),
+                    ),
+                    ErrorStyle::Mocking => (
+                        format!("'{}' needs {} argument(s), but sure, {} is close enough.", name, expected, found),
+                        "Counting is hard. PUBU understands.".to_string(),
+                    ),
+                    ErrorStyle::Uncertain => (
+                        format!("Didn't '{}' want {} argument(s)? I count {}? I think?", name, expected, found),
+                        "Maybe count the arguments again? I might be wrong?".to_string(),
+                    ),
+                };
+            },
+            
+            (PubuError::RecursionTooDeep { name, depth }, style) => {
+                return match style {
+                    ErrorStyle::Encouraging => (
+                        format!("'{}' went {} calls deep! That's impressive, but PUBU needs to stop here.", name, depth),
+                        "Make sure your recursion has a way out. You'll get there!".to_string(),
+                    ),
+                    ErrorStyle::Harsh => (
+                        format!("RECURSION LIMIT HIT IN '{}' AT {} CALLS DEEP.", name, depth),
+                        "Your recursion never ends. Fix your base case.".to_string(),
+                    ),
+                    ErrorStyle::Existential => (
+                        format!("'{}' gazed {} calls into the abyss, and the abyss called back.", name, depth),
+                        "To understand recursion, one must first find its end.".to_string(),
+                    ),
+                    ErrorStyle::Mocking => (
+                        format!("'{}' called itself {} times deep. Was the base case optional?", name, depth),
+                        "Infinite recursion: the hobby of champions.".to_string(),
+                    ),
+                    ErrorStyle::Uncertain => (
+                        format!("Um, '{}' is {} calls deep? Is it supposed to stop at some point?", name, depth),
+                        "Maybe check when it's meant to stop? If it's meant to?".to_string(),
+                    ),
+                };
+            },
+            
+            (PubuError::TypeMismatch { operator, left, right }, style) => {
+                let operands = match right {
+                    Some(right) => format!("{} and {}", left, right),
+                    None => left.to_string(),
+                };
+                
+                return match style {
+                    ErrorStyle::Encouraging => (
+                        format!("`{}` doesn't quite know what to do with {}, but it was a creative idea!", operator, operands),
+                        "Try giving it values of a kind it understands. You're nearly there!".to_string(),
+                    ),
+                    ErrorStyle::Harsh => (
+                        format!("TYPE MISMATCH. `{}` DOES NOT WORK ON {}.", operator, operands.to_uppercase()),
+                        "Numbers with numbers. Strings with strings. Learn it.".to_string(),
+                    ),
+                    ErrorStyle::Existential => (
+                        format!("`{}` was asked to unite {}, and could not.", operator, operands),
+                        "Some things are simply not meant to be combined.".to_string(),
+                    ),
+                    ErrorStyle::Mocking => (
+                        format!("Sure, let's just `{}` {}. What could go wrong?", operator, operands),
+                        "Everything, apparently. Maybe check your types next time.".to_string(),
+                    ),
+                    ErrorStyle::Uncertain => (
+                        format!("Can `{}` work on {}? I don't think it can?", operator, operands),
+                        "Maybe the types are wrong? Or the operator? One of them?".to_string(),
+                    ),
+                };
+            },
+            
+            (PubuError::OutsideLoop, ErrorStyle::Encouraging) => (
+                "You want to leave a loop, but you're not in one. Freedom is already yours!",
+                "Only break out of or skip ahead in loops. You've got this!",
+            ),
+            (PubuError::OutsideLoop, ErrorStyle::Harsh) => (
+                "THERE IS NO LOOP HERE.",
+                "Break and continue go inside loops. Nowhere else.",
+            ),
+            (PubuError::OutsideLoop, ErrorStyle::Existential) => (
+                "You seek to escape a cycle that was never there.",
+                "One cannot transcend a loop one was never in.",
+            ),
+            (PubuError::OutsideLoop, ErrorStyle::Mocking) => (
+                "Breaking out of a loop that doesn't exist? Very dramatic.",
+                "Try it inside a loop. That's where the magic happens.",
+            ),
+            (PubuError::OutsideLoop, ErrorStyle::Uncertain) => (
+                "Were we in a loop? I don't think we were in a loop?",
+                "Maybe this goes inside a loop? I'm fairly sure it does?",
             ),
             
-            PubuError::AiCriticismFailure => format!(
-                "{}\n{}\n{}", 
-                "The AI critic has deemed your code aesthetically displeasing.".red().bold(),
-                "Consider rewriting with more elegance and fewer goto statements.".yellow(),
-                self.get_random_fact().blue()
+            (PubuError::AiCriticismFailure, ErrorStyle::Encouraging) => (
+                "The AI critic had some notes, but every artist gets those!",
+                "A few touches of elegance and it'll be perfect!",
+            ),
+            (PubuError::AiCriticismFailure, ErrorStyle::Harsh) => (
+                "REJECTED BY THE AI CRITIC.",
+                "Rewrite it. Properly this time.",
+            ),
+            (PubuError::AiCriticismFailure, ErrorStyle::Existential) => (
+                "The AI critic gazed into your code, and your code gazed back.",
+                "Beauty is in the eye of the beholder, and the beholder has spoken.",
+            ),
+            (PubuError::AiCriticismFailure, ErrorStyle::Mocking) => (
+                "The AI critic has deemed your code aesthetically displeasing.",
+                "Consider rewriting with more elegance and fewer goto statements.",
+            ),
+            (PubuError::AiCriticismFailure, ErrorStyle::Uncertain) => (
+                "The AI critic didn't like it? I think? It's hard to tell with critics.",
+                "Maybe make it prettier? Or different? Something?",
             ),
             
-            PubuError::VariableRealityDistortion => format!(
-                "{}\n{}\n{}", 
-                "A variable has spontaneously changed its value due to cosmic radiation.".red().bold(),
-                "This simulation of cosmic bit-flipping is an authentic experience of real-world computing.".yellow(),
-                self.get_philosophical_quote().italic()
+            (PubuError::VariableRealityDistortion, ErrorStyle::Encouraging) => (
+                "A cosmic ray gave one of your variables a makeover!",
+                "Embrace the change. Your code is still great, just a little different!",
             ),
+            (PubuError::VariableRealityDistortion, ErrorStyle::Harsh) => (
+                "VARIABLE CORRUPTED. COSMIC RADIATION.",
+                "Deal with it. Real hardware does this too.",
+            ),
+            (PubuError::VariableRealityDistortion, ErrorStyle::Existential) => (
+                "A variable has spontaneously changed its value due to cosmic radiation.",
+                "Was it ever truly the value you assigned, or only the value you observed?",
+            ),
+            (PubuError::VariableRealityDistortion, ErrorStyle::Mocking) => (
+                "Oh no, a variable changed by itself. Whatever will you do.",
+                "This simulation of cosmic bit-flipping is an authentic experience of real-world computing.",
+            ),
+            (PubuError::VariableRealityDistortion, ErrorStyle::Uncertain) => (
+                "Did a variable just change? Or was it always like that?",
+                "I'd check your variables, maybe? Or not, they might change again?",
+            ),
+            
+            (PubuError::Located { error, .. }, _) => return self.complain(error),
             
-            PubuError::IoError(_) => String::from("A boring, conventional IO error occurred."),
+            (PubuError::SyntaxErrors(errors), _) => {
+                return errors.first().map_or_else(|| (String::new(), String::new()), |first| self.complain(first));
+            },
+            
+            (PubuError::IoError(err), style) => {
+                let headline = match style {
+                    ErrorStyle::Encouraging => "A file had a little trouble, but we'll get through it!",
+                    ErrorStyle::Harsh => "IO ERROR.",
+                    ErrorStyle::Existential => "Even files, in the end, cannot always be read.",
+                    ErrorStyle::Mocking => "A boring, conventional IO error occurred. How original.",
+                    ErrorStyle::Uncertain => "Something about a file went wrong? I think?",
+                };
+                return (headline.to_string(), err.to_string());
+            },
+        };
+        
+        (headline.to_string(), advice.to_string())
+    }
+    
+    fn complain_about_syntax(&self, message: &str, expected: &[String], found: &str, style: ErrorStyle) -> (String, String) {
+        let expected = expected.join(" or ");
+        
+        match style {
+            ErrorStyle::Encouraging => (
+                format!("Almost there! {}.", message),
+                if expected.is_empty() {
+                    "Just a tiny syntax hiccup. You're doing great!".to_string()
+                } else {
+                    format!("PUBU was hoping for {} instead of {}, but you're doing great!", expected, found)
+                },
+            ),
+            ErrorStyle::Harsh => (
+                format!("WRONG. {}.", message),
+                if expected.is_empty() {
+                    "Your syntax is wrong. Fix it.".to_string()
+                } else {
+                    format!("Expected {}. Got {}. Fix it.", expected, found)
+                },
+            ),
+            ErrorStyle::Existential => (
+                format!("Syntax error, or is it? {}.", message),
+                if expected.is_empty() {
+                    "Check your syntax, or don't. PUBU might interpret it differently next time.".to_string()
+                } else {
+                    format!("PUBU expected {} but found {}. Reality is subjective.", expected, found)
+                },
+            ),
+            ErrorStyle::Mocking => (
+                format!("Oh sure, like that was EVER going to parse. {}.", message),
+                if expected.is_empty() {
+                    "Check your syntax. Or don't, see if PUBU cares.".to_string()
+                } else {
+                    format!("{}? Bold choice, when {} was right there.", found, expected)
+                },
+            ),
+            ErrorStyle::Uncertain => (
+                format!("Um, I think there's a syntax error? {}, maybe?", message),
+                if expected.is_empty() {
+                    "Something about the syntax, I think? Or not?".to_string()
+                } else {
+                    format!("Was {} supposed to be {}? Or not?", found, expected)
+                },
+            ),
         }
     }
+    
+    /// The location of an error, and the line it happened on when the source is known
+    fn snippet(&self, span: Span) -> String {
+        let location = format!("  --> line {}, column {}", span.line, span.column).blue();
+        
+        let Some(source) = self.source.filter(|source| source.is_char_boundary(span.start)) else {
+            return location.to_string();
+        };
+        
+        let line_start = source[..span.start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = source[span.start..].find('\n').map_or(source.len(), |i| span.start + i);
+        let text = &source[line_start..line_end];
+        
+        // Underline the span, or just its first line if it goes on for a while
+        let padding = source[line_start..span.start].chars().count();
+        let width = source.get(span.start..span.end.min(line_end))
+            .map_or(0, |underlined| underlined.chars().count())
+            .max(1);
+        
+        let number = span.line.to_string();
+        let gutter = " ".repeat(number.len());
+        
+        format!(
+            "{}\n{} {}\n{} {} {}\n{} {} {}{}",
+            location,
+            gutter, "|".blue(),
+            number.blue(), "|".blue(), text,
+            gutter, "|".blue(), " ".repeat(padding), "^".repeat(width).red().bold()
+        )
+    }
 }
 
-impl fmt::Display for ErrorFormatter {
+impl fmt::Display for ErrorFormatter<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self.format_error())
     }
-} 
\ No newline at end of file
+}
@@ -0,0 +1,366 @@
+use crate::ast::{Node, LoopKind, LunarCondition, Operator, MoodType, Spanned, SpannedNode, DEFAULT_LOOP_VARIABLE};
+use crate::error::PubuError;
+use crate::lunar::Phase;
+use crate::span::Span;
+use pest::error::{Error, ErrorVariant, InputLocation, LineColLocation};
+use pest::iterators::Pair;
+use pest::pratt_parser::{Assoc, Op, PrattParser};
+use pest::Parser as _;
+use pest_derive::Parser;
+use std::sync::OnceLock;
+
+/// Parser derived from `pubu.pest`, which accepts every mood's vocabulary at once
+#[derive(Parser)]
+#[grammar = "pubu.pest"]
+pub struct PubuGrammar;
+
+/// Parses a whole program with the pest grammar and builds the same AST as
+/// the hand-written parser
+pub fn parse(source: &str) -> Result<SpannedNode, PubuError> {
+    let program = PubuGrammar::parse(Rule::program, source)
+        .map_err(|err| syntax_error(source, err))?
+        .next()
+        .ok_or_else(|| malformed(Span::default()))?;
+    
+    let span = span_of(&program);
+    let statements = program.into_inner()
+        .filter(|pair| pair.as_rule() == Rule::statement)
+        .map(build_statement)
+        .collect::<Result<Vec<_>, _>>()?;
+    
+    Ok(Spanned::new(Node::Program(statements), span))
+}
+
+fn build_statement(pair: Pair<Rule>) -> Result<SpannedNode, PubuError> {
+    let span = span_of(&pair);
+    let inner = first_inner(pair)?;
+    
+    let node = match inner.as_rule() {
+        Rule::compliment_statement => {
+            let words: Vec<&str> = inner.into_inner().map(|word| word.as_str()).collect();
+            Ok(Node::Compliment(words.join(" ")))
+        },
+        
+        Rule::philosophical_question => Ok(Node::PhilosophicalQuestion(inner.as_str().to_string())),
+        
+        Rule::mood_statement => {
+            let mut parts = inner.into_inner();
+            let mood_type = match next_pair(&mut parts, span)?.as_str() {
+                "happy" => MoodType::Happy,
+                "grumpy" => MoodType::Grumpy,
+                "philosophical" => MoodType::Philosophical,
+                "sarcastic" => MoodType::Sarcastic,
+                _ => MoodType::Confused,
+            };
+            let body = find_block(parts, span)?;
+            Ok(Node::MoodBlock(mood_type, body))
+        },
+        
+        Rule::lunar_phase_statement => {
+            let mut condition = LunarCondition::default();
+            let mut blocks = Vec::new();
+            for part in inner.into_inner() {
+                match part.as_rule() {
+                    Rule::lunar_phase_name => {
+                        let phase = Phase::from_name(part.as_str()).ok_or_else(|| malformed(span))?;
+                        condition = LunarCondition::Phase(phase);
+                    },
+                    Rule::lunar_illumination => {
+                        let mut bounds = part.into_inner()
+                            .filter(|bound| bound.as_rule() == Rule::number)
+                            .map(|bound| bound.as_str().parse::<f64>().map_err(|_| malformed(span)));
+                        let from = bounds.next().ok_or_else(|| malformed(span))??;
+                        let to = bounds.next().ok_or_else(|| malformed(span))??;
+                        condition = LunarCondition::Illumination { from, to };
+                    },
+                    Rule::block => blocks.push(build_block(part)?),
+                    _ => {},
+                }
+            }
+            
+            let mut blocks = blocks.into_iter();
+            let body = blocks.next().ok_or_else(|| malformed(span))?;
+            Ok(Node::LunarPhaseBlock(condition, body, blocks.next()))
+        },
+        
+        Rule::reality_distortion_statement => {
+            let block = inner.into_inner()
+                .find(|part| part.as_rule() == Rule::block)
+                .ok_or_else(|| malformed(span))?;
+            let block_span = span_of(&block);
+            let body = build_block(block)?;
+            Ok(Node::RealityDistortion(Box::new(Spanned::new(Node::Block(body), block_span))))
+        },
+        
+        Rule::variable_declaration => {
+            let mut parts = inner.into_inner();
+            next_pair(&mut parts, span)?; // the mood's declaration keyword
+            let name = next_pair(&mut parts, span)?.as_str().to_string();
+            next_pair(&mut parts, span)?; // the mood's assignment operator
+            let value = build_expression(next_pair(&mut parts, span)?)?;
+            Ok(Node::VariableDeclaration(name, Box::new(value)))
+        },
+        
+        Rule::assignment => {
+            let mut parts = inner.into_inner();
+            let name = next_pair(&mut parts, span)?.as_str().to_string();
+            next_pair(&mut parts, span)?; // the mood's assignment operator
+            let value = build_expression(next_pair(&mut parts, span)?)?;
+            Ok(Node::Assignment(name, Box::new(value)))
+        },
+        
+        Rule::function_declaration => {
+            let mut parts = inner.into_inner();
+            next_pair(&mut parts, span)?; // the mood's function keyword
+            let name = next_pair(&mut parts, span)?.as_str().to_string();
+            
+            let mut params = Vec::new();
+            let mut body = Vec::new();
+            for part in parts {
+                match part.as_rule() {
+                    Rule::parameter_list => {
+                        params = part.into_inner().map(|param| param.as_str().to_string()).collect();
+                    },
+                    Rule::block => body = build_block(part)?,
+                    _ => {},
+                }
+            }
+            
+            Ok(Node::FunctionDeclaration(name, params, body.into()))
+        },
+        
+        Rule::print_statement => {
+            let value = build_expression(first_inner(inner)?)?;
+            Ok(Node::Print(Box::new(value)))
+        },
+        
+        Rule::call_statement => build_call(first_inner(inner)?),
+        
+        Rule::break_statement => Ok(Node::Break),
+        
+        Rule::continue_statement => Ok(Node::Continue),
+        
+        Rule::return_statement => {
+            let value = inner.into_inner()
+                .find(|part| part.as_rule() == Rule::expression)
+                .map(build_expression)
+                .transpose()?;
+            Ok(Node::Return(value.map(Box::new)))
+        },
+        
+        Rule::if_statement => build_if(inner),
+        
+        Rule::loop_statement => {
+            let mut variable = None;
+            let mut is_range = false;
+            let mut bounds = Vec::new();
+            let mut body = Vec::new();
+            for part in inner.into_inner() {
+                match part.as_rule() {
+                    Rule::identifier => variable = Some(part.as_str().to_string()),
+                    Rule::from_keyword => is_range = true,
+                    Rule::expression => bounds.push(build_expression(part)?),
+                    Rule::block => body = build_block(part)?,
+                    _ => {},
+                }
+            }
+            
+            let mut bounds = bounds.into_iter().map(Box::new);
+            let kind = match (is_range, bounds.next()) {
+                (true, Some(from)) => LoopKind::Range {
+                    variable: variable.unwrap_or_else(|| DEFAULT_LOOP_VARIABLE.to_string()),
+                    from,
+                    to: bounds.next().ok_or_else(|| malformed(span))?,
+                },
+                (false, Some(count)) => LoopKind::Counted(count),
+                (false, None) => LoopKind::Infinite,
+                (true, None) => return Err(malformed(span)),
+            };
+            Ok(Node::Loop(kind, body))
+        },
+        
+        _ => Err(malformed(span)),
+    }?;
+    
+    Ok(Spanned::new(node, span))
+}
+
+/// Builds an if statement, whose `else` may hold a block or chain another if
+fn build_if(pair: Pair<Rule>) -> Result<Node, PubuError> {
+    let span = span_of(&pair);
+    let mut condition = None;
+    let mut blocks = Vec::new();
+    for part in pair.into_inner() {
+        match part.as_rule() {
+            Rule::expression => condition = Some(build_expression(part)?),
+            Rule::block => blocks.push(build_block(part)?),
+            Rule::if_statement => {
+                let chained_span = span_of(&part);
+                blocks.push(vec![Spanned::new(build_if(part)?, chained_span)]);
+            },
+            _ => {},
+        }
+    }
+    
+    let condition = condition.ok_or_else(|| malformed(span))?;
+    let mut blocks = blocks.into_iter();
+    let then_branch = blocks.next().ok_or_else(|| malformed(span))?;
+    Ok(Node::IfStatement(Box::new(condition), then_branch, blocks.next()))
+}
+
+fn build_block(pair: Pair<Rule>) -> Result<Vec<SpannedNode>, PubuError> {
+    pair.into_inner().map(build_statement).collect()
+}
+
+fn find_block<'a>(mut pairs: impl Iterator<Item = Pair<'a, Rule>>, span: Span) -> Result<Vec<SpannedNode>, PubuError> {
+    let block = pairs.find(|pair| pair.as_rule() == Rule::block)
+        .ok_or_else(|| malformed(span))?;
+    build_block(block)
+}
+
+fn build_expression(pair: Pair<Rule>) -> Result<SpannedNode, PubuError> {
+    precedence()
+        .map_primary(build_term)
+        .map_prefix(|op, operand| {
+            let operand = operand?;
+            let span = span_of(&op).to(operand.span);
+            let op = match op.as_rule() {
+                Rule::negation_operator => Operator::Not,
+                _ => Operator::Subtract,
+            };
+            Ok(Spanned::new(Node::UnaryOp(op, Box::new(operand)), span))
+        })
+        .map_infix(|left, op, right| {
+            let (left, right) = (left?, right?);
+            let span = left.span.to(right.span);
+            let op = match op.as_rule() {
+                Rule::disjunction_operator => Operator::Or,
+                Rule::conjunction_operator => Operator::And,
+                Rule::equality_operator => Operator::Equal,
+                Rule::inequality_operator => Operator::NotEqual,
+                Rule::greater_than_operator => Operator::GreaterThan,
+                Rule::less_than_operator => Operator::LessThan,
+                Rule::addition_operator => Operator::Add,
+                Rule::subtraction_operator => Operator::Subtract,
+                Rule::multiplication_operator => Operator::Multiply,
+                _ => Operator::Divide,
+            };
+            Ok(Spanned::new(Node::BinaryOp(Box::new(left), op, Box::new(right)), span))
+        })
+        .parse(pair.into_inner())
+}
+
+/// Operator precedence for every mood's spellings, from loosest to tightest
+fn precedence() -> &'static PrattParser<Rule> {
+    static PRECEDENCE: OnceLock<PrattParser<Rule>> = OnceLock::new();
+    
+    PRECEDENCE.get_or_init(|| {
+        PrattParser::new()
+            .op(Op::infix(Rule::disjunction_operator, Assoc::Left))
+            .op(Op::infix(Rule::conjunction_operator, Assoc::Left))
+            .op(Op::infix(Rule::equality_operator, Assoc::Left) | Op::infix(Rule::inequality_operator, Assoc::Left))
+            .op(Op::infix(Rule::greater_than_operator, Assoc::Left) | Op::infix(Rule::less_than_operator, Assoc::Left))
+            .op(Op::infix(Rule::addition_operator, Assoc::Left) | Op::infix(Rule::subtraction_operator, Assoc::Left))
+            .op(Op::infix(Rule::multiplication_operator, Assoc::Left) | Op::infix(Rule::division_operator, Assoc::Left))
+            .op(Op::prefix(Rule::negation_operator) | Op::prefix(Rule::negative_operator))
+    })
+}
+
+fn build_term(pair: Pair<Rule>) -> Result<SpannedNode, PubuError> {
+    let span = span_of(&pair);
+    let inner = first_inner(pair)?;
+    
+    let node = match inner.as_rule() {
+        Rule::literal => {
+            let literal = first_inner(inner)?;
+            let text = literal.as_str();
+            match literal.as_rule() {
+                Rule::number => Ok(Node::Number(text.parse().map_err(|_| malformed(span))?)),
+                Rule::string => Ok(Node::String(text[1..text.len() - 1].to_string())),
+                _ => Ok(Node::Boolean(text == "true")),
+            }
+        },
+        Rule::function_call => build_call(inner),
+        Rule::identifier => Ok(Node::Identifier(inner.as_str().to_string())),
+        Rule::expression => build_expression(inner).map(|expr| expr.node),
+        _ => Err(malformed(span)),
+    }?;
+    
+    Ok(Spanned::new(node, span))
+}
+
+fn build_call(pair: Pair<Rule>) -> Result<Node, PubuError> {
+    let span = span_of(&pair);
+    let mut parts = pair.into_inner();
+    let name = next_pair(&mut parts, span)?.as_str().to_string();
+    
+    let args = match parts.next() {
+        Some(list) => list.into_inner().map(build_expression).collect::<Result<Vec<_>, _>>()?,
+        None => Vec::new(),
+    };
+    
+    Ok(Node::FunctionCall(name, args))
+}
+
+/// Where a pair was found, in the same terms the hand-written lexer uses
+fn span_of(pair: &Pair<Rule>) -> Span {
+    let span = pair.as_span();
+    let (line, column) = span.start_pos().line_col();
+    Span::new(span.start(), span.end(), line, column)
+}
+
+/// Turns pest's complaint into one PUBU can point at
+fn syntax_error(source: &str, error: Error<Rule>) -> PubuError {
+    let start = match error.location {
+        InputLocation::Pos(pos) | InputLocation::Span((pos, _)) => pos,
+    };
+    let (line, column) = match error.line_col {
+        LineColLocation::Pos(pos) | LineColLocation::Span(pos, _) => pos,
+    };
+    
+    let word = source[start..].split(char::is_whitespace).next().unwrap_or("");
+    let found = if word.is_empty() {
+        "the end of the file".to_string()
+    } else {
+        format!("`{}`", word)
+    };
+    
+    let mut expected: Vec<String> = match error.variant {
+        ErrorVariant::ParsingError { positives, .. } => positives.iter().map(describe_rule).collect(),
+        ErrorVariant::CustomError { .. } => Vec::new(),
+    };
+    expected.dedup();
+    
+    PubuError::SyntaxError {
+        span: Span::new(start, start + word.len(), line, column),
+        message: "The grammar cannot make sense of this".to_string(),
+        expected,
+        found,
+    }
+}
+
+fn describe_rule(rule: &Rule) -> String {
+    match rule {
+        Rule::EOI => "the end of the file".to_string(),
+        rule => format!("{:?}", rule).replace('_', " "),
+    }
+}
+
+/// The grammar accepted something this module doesn't know how to build
+fn malformed(span: Span) -> PubuError {
+    PubuError::SyntaxError {
+        span,
+        message: "The grammar and PUBU disagree about what this means".to_string(),
+        expected: Vec::new(),
+        found: String::new(),
+    }
+}
+
+fn first_inner(pair: Pair<Rule>) -> Result<Pair<Rule>, PubuError> {
+    let span = span_of(&pair);
+    pair.into_inner().next().ok_or_else(|| malformed(span))
+}
+
+fn next_pair<'a>(pairs: &mut impl Iterator<Item = Pair<'a, Rule>>, span: Span) -> Result<Pair<'a, Rule>, PubuError> {
+    pairs.next().ok_or_else(|| malformed(span))
+}
@@ -1,157 +1,496 @@
-use crate::ast::{Node, Operator, Value, Environment, MoodType};
+use crate::ast::{Node, LoopKind, LunarCondition, SpannedNode, Value, Function, Environment, MoodType, Operator};
 use crate::error::PubuError;
 use crate::ai_critic::AiCritic;
-use rand::Rng;
-use colored::*;
+use crate::output::{Output, StdoutOutput};
+use crate::context::Context;
+use crate::lunar::Moon;
+use crate::mood::Mood;
+use std::rc::Rc;
 
-pub struct Interpreter {
+/// How many function calls may nest before PUBU gets dizzy and refuses to
+/// call any more
+pub const DEFAULT_MAX_CALL_DEPTH: usize = 1000;
+
+/// How much stack must be left before evaluating one more node, and how much
+/// to set aside when there isn't
+///
+/// Evaluation grows the stack onto the heap as it goes, so however deeply
+/// the calls and blocks nest, the call depth limit is what stops them.
+const STACK_RED_ZONE: usize = 128 * 1024;
+const STACK_SEGMENT: usize = 4 * 1024 * 1024;
+
+pub struct Interpreter<'a> {
     env: Environment,
     ai_critic: AiCritic,
+    output: Box<dyn Output + 'a>,
+    context: Context,
+    /// How many function calls are nested inside each other right now
+    call_depth: usize,
+    /// The mood the program runs in, if anyone said, then the mood blocks
+    /// being evaluated right now, innermost last
+    moods: Vec<MoodType>,
+    /// How many loops the current function call is inside
+    loop_depth: usize,
+}
+
+/// Why evaluation stopped before reaching the end of a block
+enum Unwind {
+    Error(PubuError),
+    /// A `Return` on its way to the function call it belongs to
+    Return(Value),
+    /// A `Break` on its way to the loop it belongs to
+    Break,
+    /// A `Continue` on its way to the loop it belongs to
+    Continue,
+}
+
+impl From<PubuError> for Unwind {
+    fn from(err: PubuError) -> Self {
+        Unwind::Error(err)
+    }
+}
+
+impl Default for Interpreter<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
-impl Interpreter {
+impl<'a> Interpreter<'a> {
     pub fn new() -> Self {
         Self {
             env: Environment::new(),
             ai_critic: AiCritic::new(),
+            output: Box::new(StdoutOutput),
+            context: Context::new(),
+            call_depth: 0,
+            moods: Vec::new(),
+            loop_depth: 0,
         }
     }
     
-    pub fn interpret(&mut self, program: Node) -> Result<Value, PubuError> {
+    /// Run the program in `mood`, which has its say wherever no mood block does
+    pub fn with_mood(mut self, mood: Mood) -> Self {
+        self.moods = vec![mood.into()];
+        self
+    }
+    
+    /// Send everything the program prints, and everything PUBU has to say
+    /// about it, somewhere other than standard output
+    pub fn with_output(mut self, output: impl Output + 'a) -> Self {
+        self.output = Box::new(output);
+        self
+    }
+    
+    /// Roll every die, the critic's included, from `context`
+    pub fn with_context(mut self, context: Context) -> Self {
+        self.ai_critic = AiCritic::new().with_context(context.clone());
+        self.context = context;
+        self
+    }
+    
+    pub fn interpret(&mut self, program: SpannedNode) -> Result<Value, PubuError> {
         // Random chance of failure regardless of program correctness
-        if rand::thread_rng().gen_bool(0.05) {  // 5% chance
+        if self.context.chance(0.05) {  // 5% chance
             return Err(PubuError::RandomFailure);
         }
         
-        self.evaluate(program)
+        match self.evaluate(&program) {
+            Ok(value) => Ok(value),
+            // Returning from the top level just ends the program early
+            Err(Unwind::Return(value)) => Ok(value),
+            Err(Unwind::Error(err)) => Err(err),
+            // Loops catch their own breaks, so these never get this far
+            Err(Unwind::Break | Unwind::Continue) => Err(PubuError::OutsideLoop),
+        }
+    }
+    
+    fn evaluate(&mut self, node: &SpannedNode) -> Result<Value, Unwind> {
+        let span = node.span;
+        let result = stacker::maybe_grow(STACK_RED_ZONE, STACK_SEGMENT, || self.evaluate_node(&node.node));
+        result.map_err(|unwind| match unwind {
+            Unwind::Error(err) => Unwind::Error(err.at(span)),
+            unwind => unwind,
+        })
     }
     
-    fn evaluate(&mut self, node: Node) -> Result<Value, PubuError> {
+    /// Keep the arms of this match small, since anything they hold on to is on
+    /// the stack once for every level of nesting
+    fn evaluate_node(&mut self, node: &Node) -> Result<Value, Unwind> {
         match node {
-            Node::Program(statements) => {
-                let mut result = Value::Null;
-                
-                // Check if we have enough compliments
-                let compliment_count = statements.iter()
-                    .filter(|s| matches!(s, Node::Compliment(_)))
-                    .count();
-                
-                if compliment_count < 1 {
-                    return Err(PubuError::NotEnoughCompliments);
-                }
-                
-                // Check if there are philosophical questions in functions
-                let has_functions = statements.iter()
-                    .any(|s| matches!(s, Node::FunctionDeclaration(_, _, _)));
-                
-                let has_philosophical_questions = statements.iter()
-                    .any(|s| matches!(s, Node::PhilosophicalQuestion(_)));
-                
-                if has_functions && !has_philosophical_questions {
-                    return Err(PubuError::PhilosophicalCrisis);
-                }
-                
-                // Process all statements
-                for statement in statements {
-                    result = self.evaluate(statement)?;
-                    
-                    // Sometimes distort reality between statements
-                    if rand::thread_rng().gen_bool(0.1) {  // 10% chance
-                        result = result.distort();
-                    }
-                }
-                
-                Ok(result)
-            },
+            Node::Program(statements) => self.evaluate_program(statements),
             
-            Node::Compliment(text) => {
-                // Process the compliment
-                self.env.add_compliment();
-                println!("{}", format!("PUBU appreciates your compliment: '{}'", text).green());
-                Ok(Value::String(text))
-            },
+            Node::Compliment(text) => Ok(self.appreciate(text)),
             
-            Node::PhilosophicalQuestion(question) => {
-                // Process the philosophical question
-                self.env.add_philosophical_question();
-                println!("{}", format!("PUBU ponders: '{}'", question).blue().italic());
-                Ok(Value::Whimsical(question))
-            },
+            Node::PhilosophicalQuestion(question) => Ok(self.ponder(question)),
             
-            Node::Number(n) => Ok(Value::Number(n)),
-            Node::String(s) => Ok(Value::String(s)),
-            Node::Boolean(b) => Ok(Value::Boolean(b)),
+            Node::VariableDeclaration(name, value) => self.declare(name, value),
             
-            Node::LunarPhaseBlock(statements) => {
-                // Check if the current lunar phase allows execution
-                if !crate::is_lunar_compatible() {
-                    return Err(PubuError::MoonPhaseIncompatible);
-                }
-                
-                // Execute the block's statements
-                let mut result = Value::Null;
-                for statement in statements {
-                    result = self.evaluate(statement)?;
-                }
-                
-                Ok(result)
-            },
+            Node::Assignment(name, value) => self.assign(name, value),
             
-            Node::MoodBlock(mood_type, statements) => {
-                // Process blocks with mood-specific rules
-                match mood_type {
-                    MoodType::Grumpy => {
-                        // Grumpy blocks have a chance to fail
-                        if rand::thread_rng().gen_bool(0.3) {  // 30% chance
-                            return Err(PubuError::BadMood);
-                        }
-                    },
-                    MoodType::Philosophical => {
-                        // Philosophical blocks require a philosophical question
-                        let has_philosophical_question = statements.iter()
-                            .any(|s| matches!(s, Node::PhilosophicalQuestion(_)));
-                        
-                        if !has_philosophical_question {
-                            return Err(PubuError::PhilosophicalCrisis);
-                        }
-                    },
-                    _ => {}  // Other moods don't have special rules
-                }
-                
-                // Execute the block's statements
-                let mut result = Value::Null;
-                for statement in statements {
-                    result = self.evaluate(statement)?;
-                }
-                
-                Ok(result)
-            },
+            Node::Identifier(name) => self.look_up(name),
             
-            Node::RealityDistortion(expr) => {
-                // Evaluate the expression and then distort its value
-                let value = self.evaluate(*expr)?;
-                Ok(value.distort())
-            },
+            Node::BinaryOp(left, op, right) => self.evaluate_binary(left, *op, right),
+            
+            Node::UnaryOp(op, operand) => self.evaluate_unary(*op, operand),
+            
+            Node::FunctionDeclaration(name, params, body) => Ok(self.declare_function(name, params, body)),
+            
+            Node::FunctionCall(name, args) => self.call(name, args),
+            
+            Node::Return(value) => self.return_from(value.as_deref()),
+            
+            Node::Print(value) => self.print(value),
+            
+            Node::Number(n) => Ok(Value::Number(*n)),
+            Node::String(s) => Ok(Value::String(s.clone())),
+            Node::Boolean(b) => Ok(Value::Boolean(*b)),
+            
+            Node::Block(statements) => self.evaluate_block(statements),
+            
+            Node::LunarPhaseBlock(condition, statements, otherwise) => self.evaluate_lunar_block(*condition, statements, otherwise.as_deref()),
+            
+            Node::MoodBlock(mood_type, statements) => self.evaluate_mood_block(*mood_type, statements),
+            
+            Node::IfStatement(condition, then_branch, else_branch) => self.evaluate_if(condition, then_branch, else_branch.as_deref()),
+            
+            Node::Loop(kind, body) => self.evaluate_loop(kind, body),
+            
+            Node::Break if self.loop_depth > 0 => Err(Unwind::Break),
+            Node::Continue if self.loop_depth > 0 => Err(Unwind::Continue),
+            Node::Break | Node::Continue => Err(PubuError::OutsideLoop.into()),
+            
+            Node::RealityDistortion(expr) => self.distort(expr),
             
             Node::RandomFailure => {
                 // This node always results in failure
-                Err(PubuError::RandomFailure)
+                Err(PubuError::RandomFailure.into())
             },
+        }
+    }
+    
+    fn look_up(&mut self, name: &str) -> Result<Value, Unwind> {
+        let value = self.env.get(name).or_else(|| self.look_at_the_sky(name));
+        Ok(value.ok_or_else(|| PubuError::UndefinedVariable(name.to_string()))?)
+    }
+    
+    fn evaluate_unary(&mut self, op: Operator, operand: &SpannedNode) -> Result<Value, Unwind> {
+        let operand = self.evaluate(operand)?;
+        Ok(operand.unary(op)?)
+    }
+    
+    fn return_from(&mut self, value: Option<&SpannedNode>) -> Result<Value, Unwind> {
+        let value = match value {
+            Some(value) => self.evaluate(value)?,
+            None => Value::Null,
+        };
+        Err(Unwind::Return(value))
+    }
+    
+    fn print(&mut self, value: &SpannedNode) -> Result<Value, Unwind> {
+        let value = self.evaluate(value)?;
+        self.output.print(&value.to_string());
+        Ok(Value::Null)
+    }
+    
+    fn evaluate_lunar_block(
+        &mut self,
+        condition: LunarCondition,
+        statements: &[SpannedNode],
+        otherwise: Option<&[SpannedNode]>,
+    ) -> Result<Value, Unwind> {
+        // Check if the current lunar phase allows execution
+        let moon = Moon::now(self.context.clock());
+        match otherwise {
+            _ if condition.is_met_by(&moon) => self.evaluate_block(statements),
+            Some(otherwise) => self.evaluate_block(otherwise),
+            None => Err(PubuError::MoonPhaseIncompatible.into()),
+        }
+    }
+    
+    fn evaluate_if(
+        &mut self,
+        condition: &SpannedNode,
+        then_branch: &[SpannedNode],
+        else_branch: Option<&[SpannedNode]>,
+    ) -> Result<Value, Unwind> {
+        let condition = self.evaluate(condition)?;
+        if condition.is_truthy(&mut *self.context.rng()) {
+            self.evaluate_scoped_block(then_branch)
+        } else if let Some(else_branch) = else_branch {
+            self.evaluate_scoped_block(else_branch)
+        } else {
+            Ok(Value::Null)
+        }
+    }
+    
+    fn distort(&mut self, expr: &SpannedNode) -> Result<Value, Unwind> {
+        // Evaluate the expression and then distort its value
+        let value = self.evaluate(expr)?;
+        Ok(value.distort(&mut *self.context.rng()))
+    }
+    
+    fn appreciate(&mut self, text: &str) -> Value {
+        // Process the compliment
+        self.env.add_compliment();
+        self.output.chatter(&format!("PUBU appreciates your compliment: '{}'", text));
+        Value::String(text.to_string())
+    }
+    
+    fn ponder(&mut self, question: &str) -> Value {
+        // Process the philosophical question
+        self.env.add_philosophical_question();
+        self.output.chatter(&format!("PUBU ponders: '{}'", question));
+        Value::Whimsical(question.to_string())
+    }
+    
+    /// The moon is everyone's, so `moon_phase` and `moon_illumination` can be
+    /// read anywhere nobody has declared something else by those names
+    fn look_at_the_sky(&self, name: &str) -> Option<Value> {
+        let moon = Moon::now(self.context.clock());
+        match name {
+            "moon_phase" => Some(Value::String(moon.phase.name().to_string())),
+            "moon_illumination" => Some(Value::Number(moon.illumination)),
+            _ => None,
+        }
+    }
+    
+    fn declare(&mut self, name: &str, value: &SpannedNode) -> Result<Value, Unwind> {
+        let value = self.evaluate(value)?;
+        self.env.declare(name, value.clone());
+        Ok(value)
+    }
+    
+    fn assign(&mut self, name: &str, value: &SpannedNode) -> Result<Value, Unwind> {
+        let value = self.evaluate(value)?;
+        
+        // Only variables that already exist can be changed
+        if !self.env.assign(name, value.clone()) {
+            return Err(PubuError::UndeclaredAssignment(name.to_string()).into());
+        }
+        Ok(value)
+    }
+    
+    fn evaluate_binary(&mut self, left: &SpannedNode, op: Operator, right: &SpannedNode) -> Result<Value, Unwind> {
+        let left = self.evaluate(left)?;
+        
+        // Logic gives up as soon as it knows the answer
+        match (op, &left) {
+            (Operator::And, Value::Boolean(false)) => return Ok(Value::Boolean(false)),
+            (Operator::Or, Value::Boolean(true)) => return Ok(Value::Boolean(true)),
+            _ => {},
+        }
+        
+        let right = self.evaluate(right)?;
+        Ok(left.binary(op, &right)?)
+    }
+    
+    fn evaluate_program(&mut self, statements: &[SpannedNode]) -> Result<Value, Unwind> {
+        let mut result = Value::Null;
+        
+        // Check if we have enough compliments
+        let compliment_count = statements.iter()
+            .filter(|s| matches!(s.node, Node::Compliment(_)))
+            .count();
+        
+        if compliment_count < 1 {
+            return Err(PubuError::NotEnoughCompliments.into());
+        }
+        
+        // Check if there are philosophical questions in functions
+        let has_functions = statements.iter()
+            .any(|s| matches!(s.node, Node::FunctionDeclaration(_, _, _)));
+        
+        let has_philosophical_questions = statements.iter()
+            .any(|s| matches!(s.node, Node::PhilosophicalQuestion(_)));
+        
+        if has_functions && !has_philosophical_questions {
+            return Err(PubuError::PhilosophicalCrisis.into());
+        }
+        
+        // Occasionally critique the code for no reason
+        if self.context.chance(0.2) {  // 20% chance
+            for line in self.ai_critic.critique("placeholder code") {
+                self.output.chatter(&line);
+            }
+        }
+        
+        // Process all statements
+        for statement in statements {
+            result = self.evaluate(statement)?;
             
-            // For simplicity, all other operations return placeholder values
-            // In a real implementation, they would be fully implemented
-            _ => {
-                // Simulating a basic execution for the placeholder
-                println!("{}", "PUBU is pretending to execute something...".yellow());
+            // Sometimes distort reality between statements
+            if self.context.chance(0.1) {  // 10% chance
+                result = result.distort(&mut *self.context.rng());
+            }
+        }
+        
+        Ok(result)
+    }
+    
+    /// Runs a block's statements in the enclosing scope
+    ///
+    /// Blocks that decide whether or how often their statements run, which
+    /// are function bodies, loop bodies and conditional branches, go through
+    /// `evaluate_scoped_block` instead. Everything else, mood and lunar blocks
+    /// included, declares into the scope around it.
+    fn evaluate_block(&mut self, statements: &[SpannedNode]) -> Result<Value, Unwind> {
+        let mut result = Value::Null;
+        for statement in statements {
+            result = self.evaluate(statement)?;
+        }
+        
+        Ok(result)
+    }
+    
+    /// Runs a block's statements in a scope of their own, which they can still
+    /// change the enclosing scopes' variables from
+    fn evaluate_scoped_block(&mut self, statements: &[SpannedNode]) -> Result<Value, Unwind> {
+        let scope = self.env.child();
+        self.in_scope(scope, |interpreter| interpreter.evaluate_block(statements))
+    }
+    
+    /// Runs `f` with `scope` as the current environment, then puts the old one back
+    fn in_scope<T>(&mut self, scope: Environment, f: impl FnOnce(&mut Self) -> T) -> T {
+        let outer = std::mem::replace(&mut self.env, scope);
+        let result = f(self);
+        self.env = outer;
+        result
+    }
+    
+    fn evaluate_mood_block(&mut self, mood_type: MoodType, statements: &[SpannedNode]) -> Result<Value, Unwind> {
+        // Process blocks with mood-specific rules
+        match mood_type {
+            // Grumpy blocks have a chance to fail
+            MoodType::Grumpy if self.context.chance(0.3) => {  // 30% chance
+                return Err(PubuError::BadMood.into());
+            },
+            MoodType::Philosophical => {
+                // Philosophical blocks require a philosophical question
+                let has_philosophical_question = statements.iter()
+                    .any(|s| matches!(s.node, Node::PhilosophicalQuestion(_)));
                 
-                // Occasionally critique the code for no reason
-                if rand::thread_rng().gen_bool(0.2) {  // 20% chance
-                    let code_critique = self.ai_critic.critique("placeholder code");
-                    println!("\n{}\n", code_critique);
+                if !has_philosophical_question {
+                    return Err(PubuError::PhilosophicalCrisis.into());
+                }
+            },
+            _ => {}  // Other moods don't have special rules
+        }
+        
+        self.moods.push(mood_type);
+        let result = self.evaluate_block(statements);
+        self.moods.pop();
+        result
+    }
+    
+    fn evaluate_loop(&mut self, kind: &LoopKind, body: &[SpannedNode]) -> Result<Value, Unwind> {
+        let (variable, mut count, end) = match kind {
+            LoopKind::Counted(times) => (None, 0.0, self.loop_bound(times, "times")?),
+            LoopKind::Range { variable, from, to } => {
+                let from = self.loop_bound(from, "from")?;
+                (Some(variable), from, self.loop_bound(to, "to")?)
+            },
+            LoopKind::Infinite => (None, 0.0, f64::INFINITY),
+        };
+        
+        // The count lives just outside the body, so every iteration sees it afresh
+        let scope = self.env.child();
+        self.loop_depth += 1;
+        let result = self.in_scope(scope, |interpreter| {
+            let mut result = Value::Null;
+            while count < end {
+                interpreter.before_iteration()?;
+                if let Some(variable) = variable {
+                    interpreter.env.declare(variable, Value::Number(count));
                 }
                 
-                Ok(Value::Null)
+                // Each iteration's declarations are its own
+                match interpreter.evaluate_scoped_block(body) {
+                    Ok(value) => result = value,
+                    Err(Unwind::Break) => break,
+                    Err(Unwind::Continue) => {},
+                    Err(unwind) => return Err(unwind),
+                }
+                count += 1.0;
             }
+            
+            Ok(result)
+        });
+        self.loop_depth -= 1;
+        result
+    }
+    
+    /// Evaluates a loop's count or one end of its range, which had better be a number
+    fn loop_bound(&mut self, bound: &SpannedNode, keyword: &str) -> Result<f64, Unwind> {
+        match self.evaluate(bound)? {
+            Value::Number(n) => Ok(n),
+            value => Err(PubuError::TypeMismatch {
+                operator: keyword.to_string(),
+                left: value.kind(),
+                right: None,
+            }.into()),
+        }
+    }
+    
+    /// Gives the innermost mood a say before every trip round a loop
+    fn before_iteration(&mut self) -> Result<(), Unwind> {
+        match self.moods.last() {
+            // Grumpy PUBU gets fed up with repeating itself
+            Some(MoodType::Grumpy) if self.context.chance(0.3) => {  // 30% chance
+                Err(PubuError::BadMood.into())
+            },
+            _ => Ok(()),
+        }
+    }
+    
+    fn declare_function(&mut self, name: &str, params: &[String], body: &Rc<[SpannedNode]>) -> Value {
+        // Functions are values like any other, and remember the scope they were born in
+        let function = Value::Function(Function::new(name.to_string(), params.to_vec(), Rc::clone(body), self.env.clone()));
+        self.env.declare(name, function.clone());
+        function
+    }
+    
+    fn call(&mut self, name: &str, args: &[SpannedNode]) -> Result<Value, Unwind> {
+        let function = match self.env.get(name) {
+            Some(Value::Function(function)) => function,
+            Some(value) => return Err(PubuError::NotCallable { name: name.to_string(), kind: value.kind() }.into()),
+            None => return Err(PubuError::UndefinedFunction(name.to_string()).into()),
+        };
+        
+        if args.len() != function.params.len() {
+            return Err(PubuError::ArityMismatch {
+                name: name.to_string(),
+                expected: function.params.len(),
+                found: args.len(),
+            }.into());
+        }
+        
+        // Runaway recursion becomes an error long before it uses up the memory
+        if self.call_depth >= self.context.max_call_depth() {
+            return Err(PubuError::RecursionTooDeep { name: name.to_string(), depth: self.call_depth }.into());
+        }
+        
+        let mut values = Vec::new();
+        for arg in args {
+            values.push(self.evaluate(arg)?);
+        }
+        
+        // Every call gets a fresh scope of its own, on top of the one the function was declared in
+        let scope = function.closure().child();
+        for (param, value) in function.params.iter().zip(values) {
+            scope.declare(param, value);
+        }
+        
+        // Loops around the call are none of the function's business
+        let loop_depth = std::mem::take(&mut self.loop_depth);
+        self.call_depth += 1;
+        let result = self.in_scope(scope, |interpreter| interpreter.evaluate_block(&function.body));
+        self.call_depth -= 1;
+        self.loop_depth = loop_depth;
+        
+        match result {
+            Err(Unwind::Return(value)) => Ok(value),
+            result => result,
         }
     }
 } 
\ No newline at end of file
@@ -1,6 +1,8 @@
 use crate::mood::Mood;
 use crate::error::PubuError;
-use rand::Rng;
+use crate::span::Span;
+use crate::COMPLIMENT_STEMS;
+use crate::context::Context;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum TokenType {
@@ -8,12 +10,15 @@ pub enum TokenType {
     Identifier(String),
     Number(f64),
     String(String),
+    Boolean(bool),
     
     // Keywords based on mood
     VariableDeclaration, // like "yay", "ugh", "ponder" depending on mood
     FunctionDeclaration, // like "celebration", "do_this_for_me", etc.
     Conditional,         // like "perhaps", "whatever", etc.
     Loop,                // like "again_and_again", "repeat_i_guess", etc.
+    Break,               // like "that_is_enough", "im_out", etc.
+    Continue,            // like "skip_this_one", "next", etc.
     Return,              // like "here_you_go", "take_it", etc.
     EndBlock,            // like "done", "finally", etc.
     
@@ -26,12 +31,17 @@ pub enum TokenType {
     NotEqual,
     GreaterThan,
     LessThan,
+    And,
+    Or,
+    Not,
     
     // Special PUBU constructs
     Compliment,
     PhilosophicalQuestion,
     MoodDeclaration,
     LunarPhase,
+    RealityDistortion,
+    Else,
     
     // Syntactic elements
     LeftBrace,
@@ -40,6 +50,7 @@ pub enum TokenType {
     RightParen,
     Semicolon,
     Comma,
+    QuestionMark,
     Assignment,
     
     // Misc
@@ -51,12 +62,38 @@ pub enum TokenType {
 pub struct Token {
     pub token_type: TokenType,
     pub lexeme: String,
-    pub line: usize,
+    pub span: Span,
 }
 
 impl Token {
-    pub fn new(token_type: TokenType, lexeme: String, line: usize) -> Self {
-        Self { token_type, lexeme, line }
+    pub fn new(token_type: TokenType, lexeme: String, span: Span) -> Self {
+        Self { token_type, lexeme, span }
+    }
+    
+    /// How the token reads in an error message
+    pub fn describe(&self) -> String {
+        match self.token_type {
+            TokenType::EOF => "the end of the file".to_string(),
+            _ => format!("`{}`", self.lexeme),
+        }
+    }
+}
+
+impl TokenType {
+    /// How a token of this type reads in an error message
+    pub fn describe(&self) -> String {
+        match self {
+            TokenType::LeftBrace => "`{`".to_string(),
+            TokenType::RightBrace => "`}`".to_string(),
+            TokenType::LeftParen => "`(`".to_string(),
+            TokenType::RightParen => "`)`".to_string(),
+            TokenType::Semicolon => "`;`".to_string(),
+            TokenType::Comma => "`,`".to_string(),
+            TokenType::QuestionMark => "`?`".to_string(),
+            TokenType::Identifier(_) => "a name".to_string(),
+            TokenType::EOF => "the end of the file".to_string(),
+            other => format!("{:?}", other).to_lowercase(),
+        }
     }
 }
 
@@ -66,7 +103,13 @@ pub struct Lexer {
     start: usize,
     current: usize,
     line: usize,
+    line_start: usize,
+    start_line: usize,
+    start_column: usize,
     current_mood: Mood,
+    mood_blocks: Vec<(Mood, usize)>,
+    brace_depth: usize,
+    context: Context,
 }
 
 impl Lexer {
@@ -77,132 +120,324 @@ impl Lexer {
             start: 0,
             current: 0,
             line: 1,
+            line_start: 0,
+            start_line: 1,
+            start_column: 1,
             current_mood: mood,
+            mood_blocks: Vec::new(),
+            brace_depth: 0,
+            context: Context::new(),
         }
     }
     
+    /// Roll the lexer's dice from `context`
+    pub fn with_context(mut self, context: Context) -> Self {
+        self.context = context;
+        self
+    }
+    
     pub fn scan_tokens(&mut self) -> Result<Vec<Token>, PubuError> {
         // Randomly refuse to tokenize for no reason
-        if rand::thread_rng().gen_bool(0.05) {  // 5% chance
+        if self.context.chance(0.05) {  // 5% chance
             return Err(PubuError::RandomFailure);
         }
         
-        // The general lexing would happen here
-        // This is a placeholder implementation
-        
-        // Add a placeholder compliment token - this would be actual
-        // tokenization logic in a real implementation
-        self.tokens.push(Token::new(
-            TokenType::Compliment,
-            "dear_pubu_you_look_gorgeous_today".to_string(),
-            1
-        ));
+        while !self.is_at_end() {
+            self.start = self.current;
+            self.start_line = self.line;
+            self.start_column = self.column();
+            self.scan_token()?;
+        }
         
         // Add EOF token
         self.tokens.push(Token::new(
             TokenType::EOF,
             "".to_string(),
-            self.line
+            Span::new(self.current, self.current, self.line, self.column())
         ));
         
-        Ok(self.tokens.clone())
+        Ok(std::mem::take(&mut self.tokens))
+    }
+    
+    fn scan_token(&mut self) -> Result<(), PubuError> {
+        let c = self.advance();
+        
+        match c {
+            '{' => self.open_brace(),
+            '}' => self.close_brace(),
+            '(' => self.add_token(TokenType::LeftParen),
+            ')' => self.add_token(TokenType::RightParen),
+            ';' => self.add_token(TokenType::Semicolon),
+            ',' => self.add_token(TokenType::Comma),
+            '?' => self.add_token(TokenType::QuestionMark),
+            
+            // Traditional operators, for those who cannot let go
+            '+' => self.add_token(TokenType::Plus),
+            '-' => self.add_token(TokenType::Minus),
+            '*' => self.add_token(TokenType::Times),
+            '>' => self.add_token(TokenType::GreaterThan),
+            '<' => self.add_token(TokenType::LessThan),
+            '=' if self.match_token('=') => self.add_token(TokenType::Equal),
+            '!' => {
+                if self.match_token('=') {
+                    self.add_token(TokenType::NotEqual);
+                } else {
+                    self.add_token(TokenType::Not);
+                }
+            },
+            '&' if self.match_token('&') => self.add_token(TokenType::And),
+            '|' if self.match_token('|') => self.add_token(TokenType::Or),
+            '/' => {
+                if self.match_token('/') {
+                    self.skip_line();
+                } else {
+                    self.add_token(TokenType::Divide);
+                }
+            },
+            
+            ' ' | '\r' | '\t' => {},
+            '\n' => self.new_line(),
+            
+            '"' => self.string()?,
+            
+            c if c.is_ascii_digit() => self.number(),
+            c if c.is_alphabetic() || c == '_' => self.identifier(),
+            
+            c => return Err(self.error(&format!("PUBU has never seen a `{}` before", c), &[], &format!("`{}`", c))),
+        }
+        
+        Ok(())
+    }
+    
+    fn string(&mut self) -> Result<(), PubuError> {
+        while self.peek() != '"' && !self.is_at_end() {
+            if self.advance() == '\n' {
+                self.new_line();
+            }
+        }
+        
+        if self.is_at_end() {
+            // Unterminated string, much like this sentence
+            return Err(self.error("This string never ends, much like this sentence", &["`\"`"], "the end of the file"));
+        }
+        
+        // The closing quote
+        self.advance();
+        
+        let value = self.source[self.start + 1..self.current - 1].to_string();
+        self.add_token(TokenType::String(value));
+        Ok(())
+    }
+    
+    fn number(&mut self) {
+        while self.peek().is_ascii_digit() {
+            self.advance();
+        }
+        
+        // Look for a fractional part
+        if self.peek() == '.' && self.peek_next().is_ascii_digit() {
+            self.advance();
+            while self.peek().is_ascii_digit() {
+                self.advance();
+            }
+        }
+        
+        let value = self.source[self.start..self.current].parse().unwrap_or(0.0);
+        self.add_token(TokenType::Number(value));
     }
     
-    // Helper functions for lexing would go here
+    fn identifier(&mut self) {
+        while self.peek().is_alphanumeric() || self.peek() == '_' {
+            self.advance();
+        }
+        
+        // An identifier ending in a question mark is a philosophical question
+        if self.peek() == '?' {
+            self.advance();
+            self.add_token(TokenType::PhilosophicalQuestion);
+            return;
+        }
+        
+        let text = &self.source[self.start..self.current];
+        
+        if self.is_comment_marker(text) {
+            self.skip_line();
+            return;
+        }
+        
+        let token_type = self.operator_type(text)
+            .unwrap_or_else(|| self.identifier_type(text));
+        self.add_token(token_type);
+    }
+    
+    fn new_line(&mut self) {
+        self.line += 1;
+        self.line_start = self.current;
+    }
+    
+    /// 1-based column of the current position, counted in characters
+    fn column(&self) -> usize {
+        self.source[self.line_start..self.current].chars().count() + 1
+    }
+    
+    fn skip_line(&mut self) {
+        while self.peek() != '\n' && !self.is_at_end() {
+            self.advance();
+        }
+    }
     
     fn is_at_end(&self) -> bool {
         self.current >= self.source.len()
     }
     
+    fn peek(&self) -> char {
+        self.source[self.current..].chars().next().unwrap_or('\0')
+    }
+    
+    fn peek_next(&self) -> char {
+        self.source[self.current..].chars().nth(1).unwrap_or('\0')
+    }
+    
     fn advance(&mut self) -> char {
-        let c = self.source.chars().nth(self.current).unwrap_or('\0');
-        self.current += 1;
+        let c = self.peek();
+        self.current += c.len_utf8();
         c
     }
     
     fn add_token(&mut self, token_type: TokenType) {
         let text = &self.source[self.start..self.current];
-        self.tokens.push(Token::new(token_type, text.to_string(), self.line));
+        let span = Span::new(self.start, self.current, self.start_line, self.start_column);
+        self.tokens.push(Token::new(token_type, text.to_string(), span));
+    }
+    
+    /// A syntax error about the text scanned since the start of the current token
+    fn error(&self, message: &str, expected: &[&str], found: &str) -> PubuError {
+        PubuError::SyntaxError {
+            span: Span::new(self.start, self.current, self.start_line, self.start_column),
+            message: message.to_string(),
+            expected: expected.iter().map(|e| e.to_string()).collect(),
+            found: found.to_string(),
+        }
     }
     
     fn match_token(&mut self, expected: char) -> bool {
         if self.is_at_end() { return false; }
-        if self.source.chars().nth(self.current) != Some(expected) { return false; }
+        if self.peek() != expected { return false; }
         
-        self.current += 1;
+        self.current += expected.len_utf8();
         true
     }
     
+    fn is_comment_marker(&self, text: &str) -> bool {
+        text == self.active_mood().get_keywords().comment_marker
+    }
+    
+    /// The mood of the innermost `<mood> mood { ... }` block, or the
+    /// interpreter's own mood outside of any block
+    fn active_mood(&self) -> Mood {
+        self.mood_blocks.last()
+            .map(|(mood, _)| *mood)
+            .unwrap_or(self.current_mood)
+    }
+    
+    fn open_brace(&mut self) {
+        self.brace_depth += 1;
+        
+        // `<mood> mood {` switches vocabulary until the matching brace
+        if let [.., declaration, marker] = self.tokens.as_slice() {
+            if declaration.token_type == TokenType::MoodDeclaration && marker.lexeme == "mood" {
+                if let Some(mood) = Mood::from_name(&declaration.lexeme) {
+                    self.mood_blocks.push((mood, self.brace_depth));
+                }
+            }
+        }
+        
+        self.add_token(TokenType::LeftBrace);
+    }
+    
+    fn close_brace(&mut self) {
+        if matches!(self.mood_blocks.last(), Some((_, depth)) if *depth == self.brace_depth) {
+            self.mood_blocks.pop();
+        }
+        self.brace_depth = self.brace_depth.saturating_sub(1);
+        
+        self.add_token(TokenType::RightBrace);
+    }
+    
     // Detect mood-specific keywords
     fn identifier_type(&self, text: &str) -> TokenType {
-        match self.current_mood {
-            Mood::Happy => {
-                match text {
-                    "yay" => TokenType::VariableDeclaration,
-                    "celebration" => TokenType::FunctionDeclaration,
-                    "perhaps" => TokenType::Conditional,
-                    "again_and_again" => TokenType::Loop,
-                    "here_you_go" => TokenType::Return,
-                    "done" => TokenType::EndBlock,
-                    "happy" => TokenType::MoodDeclaration,
-                    "dear_pubu" | "gorgeous" | "brilliant" | "amazing" => TokenType::Compliment,
-                    _ => TokenType::Identifier(text.to_string()),
-                }
-            },
-            Mood::Grumpy => {
-                match text {
-                    "ugh" => TokenType::VariableDeclaration,
-                    "do_this_for_me" => TokenType::FunctionDeclaration,
-                    "whatever" => TokenType::Conditional,
-                    "repeat_i_guess" => TokenType::Loop,
-                    "take_it" => TokenType::Return,
-                    "finally" => TokenType::EndBlock,
-                    "grumpy" => TokenType::MoodDeclaration,
-                    "fine_pubu" | "not_bad" | "decent" => TokenType::Compliment,
-                    _ => TokenType::Identifier(text.to_string()),
-                }
-            },
-            // Additional moods would be handled similarly
-            _ => TokenType::Identifier(text.to_string()), // Simplified
+        // Some words mean the same thing no matter how PUBU feels
+        if Mood::from_name(text).is_some() {
+            return TokenType::MoodDeclaration;
+        }
+        
+        match text {
+            "lunar_phase" => return TokenType::LunarPhase,
+            "reality_distortion" => return TokenType::RealityDistortion,
+            "else" => return TokenType::Else,
+            "true" => return TokenType::Boolean(true),
+            "false" => return TokenType::Boolean(false),
+            _ => {},
+        }
+        
+        // Compound compliments are left to the parser, since they could also be variable names
+        if COMPLIMENT_STEMS.contains(&text) {
+            return TokenType::Compliment;
+        }
+        
+        let keywords = self.active_mood().get_keywords();
+        
+        if text == keywords.variable_declaration {
+            TokenType::VariableDeclaration
+        } else if text == keywords.function_declaration {
+            TokenType::FunctionDeclaration
+        } else if text == keywords.conditional {
+            TokenType::Conditional
+        } else if text == keywords.loop_construct {
+            TokenType::Loop
+        } else if text == keywords.break_loop {
+            TokenType::Break
+        } else if text == keywords.continue_loop {
+            TokenType::Continue
+        } else if text == keywords.return_statement {
+            TokenType::Return
+        } else if text == keywords.end_block {
+            TokenType::EndBlock
+        } else if text == keywords.assignment {
+            TokenType::Assignment
+        } else {
+            TokenType::Identifier(text.to_string())
         }
     }
     
     // Detect operators based on mood
     fn operator_type(&self, text: &str) -> Option<TokenType> {
-        match self.current_mood {
-            Mood::Happy => {
-                match text {
-                    "plus" => Some(TokenType::Plus),
-                    "minus" => Some(TokenType::Minus),
-                    "times" => Some(TokenType::Times),
-                    "divided_by" => Some(TokenType::Divide),
-                    "same_as" => Some(TokenType::Equal),
-                    "different_from" => Some(TokenType::NotEqual),
-                    _ => None,
-                }
-            },
-            Mood::Grumpy => {
-                match text {
-                    "add" => Some(TokenType::Plus),
-                    "subtract" => Some(TokenType::Minus),
-                    "multiply" => Some(TokenType::Times),
-                    "divide" => Some(TokenType::Divide),
-                    "equals" => Some(TokenType::Equal),
-                    "not_equals" => Some(TokenType::NotEqual),
-                    _ => None,
-                }
-            },
-            // Additional moods would be handled similarly
-            _ => None, // Simplified
+        let operators = self.active_mood().get_operators();
+        
+        if text == operators.addition {
+            Some(TokenType::Plus)
+        } else if text == operators.subtraction {
+            Some(TokenType::Minus)
+        } else if text == operators.multiplication {
+            Some(TokenType::Times)
+        } else if text == operators.division {
+            Some(TokenType::Divide)
+        } else if text == operators.equality {
+            Some(TokenType::Equal)
+        } else if text == operators.inequality {
+            Some(TokenType::NotEqual)
+        } else if text == operators.greater_than {
+            Some(TokenType::GreaterThan)
+        } else if text == operators.less_than {
+            Some(TokenType::LessThan)
+        } else if text == operators.conjunction {
+            Some(TokenType::And)
+        } else if text == operators.disjunction {
+            Some(TokenType::Or)
+        } else if text == operators.negation {
+            Some(TokenType::Not)
+        } else {
+            None
         }
     }
-    
-    // Detect philosophical questions
-    fn is_philosophical_question(&self, text: &str) -> bool {
-        text.ends_with("?") && 
-        (text.contains("why") || 
-         text.contains("meaning") || 
-         text.contains("purpose") || 
-         text.contains("existence"))
-    }
-} 
\ No newline at end of file
+}
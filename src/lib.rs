@@ -2,31 +2,40 @@ pub mod ast;
 pub mod interpreter;
 pub mod lexer;
 pub mod parser;
+pub mod grammar;
 pub mod error;
 pub mod mood;
 pub mod ai_critic;
 pub mod utils;
+pub mod span;
+pub mod output;
+pub mod context;
+pub mod clock;
+pub mod lunar;
 
-use std::time::{SystemTime, UNIX_EPOCH};
-use rand::Rng;
+use clock::Clock;
 
 /// The PUBU language version
 pub const VERSION: &str = "0.1.0";
 
+/// Which front end turns source code into an AST
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum Frontend {
+    /// The mood-aware lexer and recursive-descent parser
+    #[default]
+    HandWritten,
+    /// The parser derived from `pubu.pest`, which accepts every mood at once
+    Pest,
+}
+
 /// Determines if a function should succeed based on random chance
-pub fn function_succeeds() -> bool {
-    let mut rng = rand::thread_rng();
-    rng.gen_bool(0.8) // 80% chance of success
+pub fn function_succeeds(context: &context::Context) -> bool {
+    !context.chance(0.2) // 80% chance of success
 }
 
-/// Gets the current mood of the interpreter based on system time
-pub fn get_current_mood() -> mood::Mood {
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_secs();
-    
-    match now % 5 {
+/// Gets the mood of the interpreter at the time `clock` shows
+pub fn get_current_mood(clock: &dyn Clock) -> mood::Mood {
+    match clock.unix_seconds() % 5 {
         0 => mood::Mood::Happy,
         1 => mood::Mood::Grumpy,
         2 => mood::Mood::Philosophical,
@@ -35,45 +44,97 @@ pub fn get_current_mood() -> mood::Mood {
     }
 }
 
+/// Fragments that turn an identifier into a compliment, wherever they appear in it,
+/// so `dear_pubu_you_look_gorgeous_today` and `i_suppose_pubu_is_functional` both count
+///
+/// `compliment_stem` in `pubu.pest` lists the same fragments.
+pub const COMPLIMENT_STEMS: [&str; 15] = [
+    "dear_pubu", "pubu_is", "pubu_your", "pubu_you", "thank_you_pubu", "fine_pubu",
+    "gorgeous", "brilliant", "amazing", "magnificent", "wonderful", "clever", "smart",
+    "not_bad", "decent",
+];
+
+/// Checks whether a single identifier compliments PUBU
+pub fn is_compliment(word: &str) -> bool {
+    COMPLIMENT_STEMS.iter().any(|stem| word.contains(stem))
+}
+
 /// Counts compliments in the source code
 pub fn count_compliments(source: &str) -> usize {
-    let compliment_keywords = [
-        "dear_pubu", "gorgeous", "brilliant", "amazing", 
-        "magnificent", "wonderful", "clever", "smart"
-    ];
-    
-    compliment_keywords.iter()
-        .map(|keyword| source.matches(keyword).count())
-        .sum()
+    source.split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|word| is_compliment(word))
+        .count()
 }
 
-/// Checks if code can run based on the current phase of the moon
-pub fn is_lunar_compatible() -> bool {
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_secs() / 86400; // Days since epoch
-    
-    // Simplified moon phase algorithm (completely unscientific)
-    let moon_phase = now % 30;
-    moon_phase > 12 && moon_phase < 18 // Around full moon
+/// Checks if code can run based on the phase of the moon at the time `clock` shows
+///
+/// Lunar code only runs under a full moon, a real one.
+pub fn is_lunar_compatible(clock: &dyn Clock) -> bool {
+    lunar::Moon::now(clock).phase == lunar::Phase::Full
+}
+
+/// Parses source code into a `Node::Program` with the chosen front end
+pub fn parse(source: &str, mood: mood::Mood, frontend: Frontend) -> Result<ast::SpannedNode, error::PubuError> {
+    parse_in(source, mood, frontend, &context::Context::new())
+}
+
+/// Parses source code like `parse`, rolling every die from `context`
+pub fn parse_in(
+    source: &str,
+    mood: mood::Mood,
+    frontend: Frontend,
+    context: &context::Context,
+) -> Result<ast::SpannedNode, error::PubuError> {
+    match frontend {
+        Frontend::HandWritten => {
+            let tokens = lexer::Lexer::new(source.to_string(), mood)
+                .with_context(context.clone())
+                .scan_tokens()?;
+            parser::Parser::new(tokens, mood).with_context(context.clone()).parse()
+        },
+        Frontend::Pest => grammar::parse(source),
+    }
 }
 
 /// Evaluates source code with PUBU's absurd rules
-pub fn evaluate(source: &str) -> Result<String, error::PubuError> {
-    // Count compliments
+///
+/// The source is tokenized, parsed and interpreted in the given mood, and the
+/// value of the last statement is returned. Whatever the program prints goes
+/// to standard output.
+pub fn evaluate(source: &str, mood: mood::Mood) -> Result<ast::Value, error::PubuError> {
+    evaluate_with(source, mood, Frontend::default())
+}
+
+/// Evaluates source code like `evaluate`, parsing it with the chosen front end
+pub fn evaluate_with(source: &str, mood: mood::Mood, frontend: Frontend) -> Result<ast::Value, error::PubuError> {
+    evaluate_in(source, mood, frontend, &context::Context::new(), &mut output::StdoutOutput)
+}
+
+/// Evaluates source code like `evaluate_with`, rolling every die from `context`,
+/// so the same seed gives the same run, and printing to `output`
+pub fn evaluate_in(
+    source: &str,
+    mood: mood::Mood,
+    frontend: Frontend,
+    context: &context::Context,
+    output: &mut dyn output::Output,
+) -> Result<ast::Value, error::PubuError> {
+    // Count compliments before bothering with anything else
     let compliment_count = count_compliments(source);
     if compliment_count < 1 {
         return Err(error::PubuError::NotEnoughCompliments);
     }
     
     // Check mood
-    let mood = get_current_mood();
-    if matches!(mood, mood::Mood::Grumpy) && rand::random::<f32>() > 0.3 {
+    if matches!(mood, mood::Mood::Grumpy) && context.chance(0.7) {
         return Err(error::PubuError::BadMood);
     }
     
-    // TODO: Actual parsing and evaluation
+    let program = parse_in(source, mood, frontend, context)?;
     
-    Ok("PUBU execution probably succeeded, but who knows?".to_string())
-} 
\ No newline at end of file
+    interpreter::Interpreter::new()
+        .with_mood(mood)
+        .with_context(context.clone())
+        .with_output(output)
+        .interpret(program)
+}
@@ -0,0 +1,115 @@
+use crate::clock::Clock;
+use std::f64::consts::TAU;
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Days from one new moon to the next, on average
+pub const SYNODIC_MONTH_DAYS: f64 = 29.530588853;
+
+/// Seconds after the Unix epoch of the new moon of 2000-01-06 18:14 UTC,
+/// which every other phase is counted from
+const KNOWN_NEW_MOON: f64 = 947_182_440.0;
+
+const SECONDS_PER_DAY: f64 = 86_400.0;
+
+/// The eight phases of the moon, starting from new
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    New,
+    WaxingCrescent,
+    FirstQuarter,
+    WaxingGibbous,
+    Full,
+    WaningGibbous,
+    LastQuarter,
+    WaningCrescent,
+}
+
+impl Phase {
+    /// Every phase, in the order the moon goes through them
+    pub const ALL: [Phase; 8] = [
+        Phase::New,
+        Phase::WaxingCrescent,
+        Phase::FirstQuarter,
+        Phase::WaxingGibbous,
+        Phase::Full,
+        Phase::WaningGibbous,
+        Phase::LastQuarter,
+        Phase::WaningCrescent,
+    ];
+    
+    /// The name PUBU code uses for the phase, as in `lunar_phase full { }`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Phase::New => "new",
+            Phase::WaxingCrescent => "waxing_crescent",
+            Phase::FirstQuarter => "first_quarter",
+            Phase::WaxingGibbous => "waxing_gibbous",
+            Phase::Full => "full",
+            Phase::WaningGibbous => "waning_gibbous",
+            Phase::LastQuarter => "last_quarter",
+            Phase::WaningCrescent => "waning_crescent",
+        }
+    }
+    
+    /// Look up a phase by the name PUBU code uses for it
+    pub fn from_name(name: &str) -> Option<Phase> {
+        Phase::ALL.into_iter().find(|phase| phase.name() == name)
+    }
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Phase::New => "new moon",
+            Phase::WaxingCrescent => "waxing crescent",
+            Phase::FirstQuarter => "first quarter",
+            Phase::WaxingGibbous => "waxing gibbous",
+            Phase::Full => "full moon",
+            Phase::WaningGibbous => "waning gibbous",
+            Phase::LastQuarter => "last quarter",
+            Phase::WaningCrescent => "waning crescent",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// The moon as seen at one instant
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Moon {
+    /// Days since the last new moon
+    pub age: f64,
+    pub phase: Phase,
+    /// How much of the moon is lit, from 0.0 at new moon to 1.0 at full moon
+    pub illumination: f64,
+}
+
+impl Moon {
+    /// The moon at `time`, from the mean length of the synodic month
+    ///
+    /// Real lunations wander up to half a day either side of the mean, which is
+    /// close enough for deciding whether code may run.
+    pub fn at(time: SystemTime) -> Moon {
+        let seconds = match time.duration_since(UNIX_EPOCH) {
+            Ok(since) => since.as_secs_f64(),
+            Err(err) => -err.duration().as_secs_f64(),
+        };
+        
+        let age = ((seconds - KNOWN_NEW_MOON) / SECONDS_PER_DAY).rem_euclid(SYNODIC_MONTH_DAYS);
+        let fraction = age / SYNODIC_MONTH_DAYS;
+        
+        // Each phase covers an eighth of the month, centred on its namesake moment
+        let index = (fraction * 8.0 + 0.5).floor() as usize % 8;
+        
+        Moon {
+            age,
+            phase: Phase::ALL[index],
+            illumination: (1.0 - (fraction * TAU).cos()) / 2.0,
+        }
+    }
+    
+    /// The moon at the time `clock` shows
+    pub fn now(clock: &dyn Clock) -> Moon {
+        Moon::at(clock.now())
+    }
+}
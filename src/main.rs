@@ -1,10 +1,15 @@
-use pubu::{VERSION, evaluate, get_current_mood};
+use pubu::{VERSION, Frontend, evaluate_in, get_current_mood, parse_in};
+use pubu::clock::FixedClock;
+use pubu::context::Context;
+use pubu::output::StdoutOutput;
+use pubu::interpreter::DEFAULT_MAX_CALL_DEPTH;
 use colored::*;
 use clap::{Parser, Subcommand};
 use std::fs;
 use std::path::PathBuf;
 use rustyline::DefaultEditor;
 use rand::seq::SliceRandom;
+use rand::Rng;
 
 #[derive(Parser)]
 #[command(
@@ -13,6 +18,22 @@ use rand::seq::SliceRandom;
     version = VERSION,
 )]
 struct Cli {
+    /// Which parser should make sense of your code
+    #[arg(long, value_enum, default_value_t = Frontend::HandWritten, global = true)]
+    frontend: Frontend,
+    
+    /// Roll every one of PUBU's dice from this seed, so a run can be repeated exactly
+    #[arg(long, global = true)]
+    seed: Option<u64>,
+    
+    /// Run as if it were this many seconds after the Unix epoch, which settles PUBU's mood and the moon
+    #[arg(long, value_name = "TIMESTAMP", global = true)]
+    at: Option<u64>,
+    
+    /// How many function calls may nest before PUBU calls it runaway recursion
+    #[arg(long, value_name = "CALLS", default_value_t = DEFAULT_MAX_CALL_DEPTH, global = true)]
+    max_call_depth: usize,
+    
     #[command(subcommand)]
     command: Command,
 }
@@ -25,6 +46,12 @@ enum Command {
         #[arg(value_name = "FILE")]
         file: PathBuf,
     },
+    /// Report every syntax error in a PUBU source file without running it
+    Check {
+        /// Path to the source file
+        #[arg(value_name = "FILE")]
+        file: PathBuf,
+    },
     /// Start an interactive PUBU REPL
     Repl,
     /// Display a philosophical thought about programming
@@ -32,45 +59,78 @@ enum Command {
 }
 
 fn main() {
+    let cli = Cli::parse();
+    let mut context = cli.seed.map(Context::seeded).unwrap_or_default().with_max_call_depth(cli.max_call_depth);
+    if let Some(timestamp) = cli.at {
+        context = context.with_clock(FixedClock::at_unix_seconds(timestamp));
+    }
+    // A check that fails at random tells nobody anything about their code
+    if let Command::Check { .. } = cli.command {
+        context = context.without_chaos();
+    }
+    
     // Add a random delay to startup because why not?
-    let startup_delay = rand::random::<u64>() % 3000;
+    let startup_delay = context.rng().gen_range(0..3000);
     std::thread::sleep(std::time::Duration::from_millis(startup_delay));
     
     let greeting = format!("PUBU v{} - Probably Useful But Useless", VERSION).cyan().bold();
     println!("{}", greeting);
     
     // 10% chance of printing a dismissive message and immediately exiting
-    if rand::random::<f32>() < 0.1 {
+    if context.chance(0.1) {
         println!("{}", "I don't feel like running today. Try again later.".red());
         return;
     }
 
-    let cli = Cli::parse();
-
     match cli.command {
-        Command::Run { file } => run_file(file),
-        Command::Repl => start_repl(),
-        Command::Wisdom => display_wisdom(),
+        Command::Run { file } => run_file(file, cli.frontend, &context),
+        Command::Check { file } => check_file(file, cli.frontend, &context),
+        Command::Repl => start_repl(cli.frontend, &context),
+        Command::Wisdom => display_wisdom(&context),
     }
 }
 
-fn run_file(path: PathBuf) {
+fn run_file(path: PathBuf, frontend: Frontend, context: &Context) {
     match fs::read_to_string(&path) {
         Ok(content) => {
+            let mood = get_current_mood(context.clock());
             println!("Running file: {}", path.display());
-            println!("Current mood: {:?}", get_current_mood());
+            println!("Current mood: {:?}", mood);
             
-            match evaluate(&content) {
-                Ok(result) => println!("{}", result.green()),
-                Err(err) => println!("{}", format!("Error: {}", err).red()),
+            match evaluate_in(&content, mood, frontend, context, &mut StdoutOutput) {
+                Ok(result) => println!("{}", result.to_string().green()),
+                Err(err) => println!("{}", format!("Error: {}", err.render(&content, mood, context)).red()),
             }
         }
         Err(err) => println!("{}", format!("Failed to read file: {}", err).red()),
     }
 }
 
-fn start_repl() {
-    println!("Welcome to the PUBU REPL (mood: {:?})", get_current_mood());
+fn check_file(path: PathBuf, frontend: Frontend, context: &Context) {
+    match fs::read_to_string(&path) {
+        Ok(content) => {
+            let mood = get_current_mood(context.clock());
+            println!("Checking file: {}", path.display());
+            println!("Current mood: {:?}", mood);
+            
+            match parse_in(&content, mood, frontend, context) {
+                Ok(_) => println!("{}", "No syntax errors. Suspicious, but fine.".green()),
+                Err(err) => {
+                    println!("{}", format!("Error: {}", err.render(&content, mood, context)).red());
+                    std::process::exit(1);
+                },
+            }
+        }
+        Err(err) => {
+            println!("{}", format!("Failed to read file: {}", err).red());
+            std::process::exit(1);
+        },
+    }
+}
+
+fn start_repl(frontend: Frontend, context: &Context) {
+    let mood = get_current_mood(context.clock());
+    println!("Welcome to the PUBU REPL (mood: {:?})", mood);
     println!("Type 'exit' to quit");
     println!("Remember to compliment the interpreter!");
     
@@ -82,7 +142,7 @@ fn start_repl() {
             Ok(line) => {
                 if line.trim() == "exit" {
                     // 20% chance of refusing to exit
-                    if rand::random::<f32>() < 0.2 {
+                    if context.chance(0.2) {
                         println!("{}", "I'm not done with you yet. Type 'exit' again if you really mean it.".yellow());
                         continue;
                     }
@@ -92,9 +152,9 @@ fn start_repl() {
                 let _ = rl.add_history_entry(line.as_str());
                 
                 // Evaluate the input with PUBU's absurd rules
-                match evaluate(&line) {
-                    Ok(result) => println!("{}", result.green()),
-                    Err(err) => println!("{}", format!("Error: {}", err).red()),
+                match evaluate_in(&line, mood, frontend, context, &mut StdoutOutput) {
+                    Ok(result) => println!("{}", result.to_string().green()),
+                    Err(err) => println!("{}", format!("Error: {}", err.render(&line, mood, context)).red()),
                 }
             }
             Err(_) => break,
@@ -104,7 +164,7 @@ fn start_repl() {
     println!("Goodbye... for now.");
 }
 
-fn display_wisdom() {
+fn display_wisdom(context: &Context) {
     let wisdoms = [
         "The best code is the code you delete.",
         "If your code works, don't touch it. Ever. Seriously.",
@@ -118,11 +178,11 @@ fn display_wisdom() {
         "PUBU doesn't have bugs; it has intentional deviations from expected behavior."
     ];
     
-    let wisdom = wisdoms.choose(&mut rand::thread_rng()).unwrap();
+    let wisdom = wisdoms.choose(&mut *context.rng()).unwrap();
     println!("{}", wisdom.yellow().italic());
     
     // 30% chance of adding a completely unrelated follow-up
-    if rand::random::<f32>() < 0.3 {
+    if context.chance(0.3) {
         println!("{}", "Also, did you know that giraffes have the same number of neck vertebrae as humans?".blue());
     }
 } 
\ No newline at end of file
@@ -8,6 +8,27 @@ pub enum Mood {
 }
 
 impl Mood {
+    /// Every mood PUBU can be in
+    pub const ALL: [Mood; 5] = [
+        Mood::Happy,
+        Mood::Grumpy,
+        Mood::Philosophical,
+        Mood::Sarcastic,
+        Mood::Confused,
+    ];
+    
+    /// Look up a mood by the name used to open a `<name> mood { ... }` block
+    pub fn from_name(name: &str) -> Option<Mood> {
+        match name {
+            "happy" => Some(Mood::Happy),
+            "grumpy" => Some(Mood::Grumpy),
+            "philosophical" => Some(Mood::Philosophical),
+            "sarcastic" => Some(Mood::Sarcastic),
+            "confused" => Some(Mood::Confused),
+            _ => None,
+        }
+    }
+    
     /// Get the keywords that replace standard language constructs based on mood
     pub fn get_keywords(&self) -> MoodKeywords {
         match self {
@@ -16,6 +37,8 @@ impl Mood {
                 function_declaration: "celebration",
                 conditional: "perhaps",
                 loop_construct: "again_and_again",
+                break_loop: "that_is_enough",
+                continue_loop: "skip_this_one",
                 return_statement: "here_you_go",
                 end_block: "done",
                 assignment: "is",
@@ -27,6 +50,8 @@ impl Mood {
                 function_declaration: "do_this_for_me",
                 conditional: "whatever",
                 loop_construct: "repeat_i_guess",
+                break_loop: "im_out",
+                continue_loop: "next",
                 return_statement: "take_it",
                 end_block: "finally",
                 assignment: "equals",
@@ -38,6 +63,8 @@ impl Mood {
                 function_declaration: "essence_of",
                 conditional: "what_if",
                 loop_construct: "eternal_return",
+                break_loop: "transcend",
+                continue_loop: "let_it_pass",
                 return_statement: "conclude",
                 end_block: "fin",
                 assignment: "becomes",
@@ -49,6 +76,8 @@ impl Mood {
                 function_declaration: "try_doing",
                 conditional: "as_if",
                 loop_construct: "round_and_round",
+                break_loop: "oh_please_stop",
+                continue_loop: "yeah_skip_it",
                 return_statement: "there_ya_go",
                 end_block: "whoopee",
                 assignment: "totally_equals",
@@ -60,6 +89,8 @@ impl Mood {
                 function_declaration: "somehow",
                 conditional: "maybe",
                 loop_construct: "do_more",
+                break_loop: "wait_stop",
+                continue_loop: "skip_maybe",
                 return_statement: "is_this_right",
                 end_block: "i_think_im_done",
                 assignment: "could_be",
@@ -89,6 +120,11 @@ impl Mood {
                 division: "divided_by",
                 equality: "same_as",
                 inequality: "different_from",
+                greater_than: "bigger_than",
+                less_than: "smaller_than",
+                conjunction: "and_also",
+                disjunction: "or_maybe",
+                negation: "not_really",
             },
             
             Mood::Grumpy => MoodOperators {
@@ -98,6 +134,11 @@ impl Mood {
                 division: "divide",
                 equality: "equals",
                 inequality: "not_equals",
+                greater_than: "more_than",
+                less_than: "less_than",
+                conjunction: "and",
+                disjunction: "or",
+                negation: "not",
             },
             
             Mood::Philosophical => MoodOperators {
@@ -107,6 +148,11 @@ impl Mood {
                 division: "diminish",
                 equality: "identical",
                 inequality: "distinct",
+                greater_than: "transcends",
+                less_than: "precedes",
+                conjunction: "in_unity_with",
+                disjunction: "or_alternatively",
+                negation: "negate",
             },
             
             Mood::Sarcastic => MoodOperators {
@@ -116,6 +162,11 @@ impl Mood {
                 division: "share",
                 equality: "sure_same",
                 inequality: "obviously_different",
+                greater_than: "way_bigger_than",
+                less_than: "way_smaller_than",
+                conjunction: "as_well_as",
+                disjunction: "or_whatever",
+                negation: "yeah_no",
             },
             
             Mood::Confused => MoodOperators {
@@ -125,6 +176,11 @@ impl Mood {
                 division: "split",
                 equality: "is_it_same",
                 inequality: "is_it_different",
+                greater_than: "bigger_maybe",
+                less_than: "smaller_maybe",
+                conjunction: "and_maybe",
+                disjunction: "or_something",
+                negation: "not_sure",
             },
         }
     }
@@ -135,6 +191,8 @@ pub struct MoodKeywords {
     pub function_declaration: &'static str,
     pub conditional: &'static str,
     pub loop_construct: &'static str,
+    pub break_loop: &'static str,
+    pub continue_loop: &'static str,
     pub return_statement: &'static str,
     pub end_block: &'static str, 
     pub assignment: &'static str,
@@ -148,8 +206,14 @@ pub struct MoodOperators {
     pub division: &'static str,
     pub equality: &'static str,
     pub inequality: &'static str,
+    pub greater_than: &'static str,
+    pub less_than: &'static str,
+    pub conjunction: &'static str,
+    pub disjunction: &'static str,
+    pub negation: &'static str,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ErrorStyle {
     Encouraging, // "Don't worry! You just forgot a semicolon. You're doing great!"
     Harsh,       // "WRONG. You made an obvious mistake on line 42."
@@ -0,0 +1,86 @@
+use colored::*;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Somewhere for PUBU to put what it has to say
+///
+/// Programs `print`, and the interpreter chatters about compliments, questions
+/// and critiques whether anyone asked or not. Both arrive one line at a time,
+/// as plain text. Dressing them up is up to the output.
+pub trait Output {
+    /// A line the program printed on purpose
+    fn print(&mut self, line: &str);
+    
+    /// A line of the interpreter's own commentary
+    fn chatter(&mut self, line: &str) {
+        self.print(line);
+    }
+}
+
+/// Writes everything to standard output, which is what `pubu` itself uses,
+/// with PUBU's own commentary set apart in colour
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StdoutOutput;
+
+impl Output for StdoutOutput {
+    fn print(&mut self, line: &str) {
+        println!("{}", line);
+    }
+    
+    fn chatter(&mut self, line: &str) {
+        println!("{}", line.blue().italic());
+    }
+}
+
+impl<O: Output + ?Sized> Output for &mut O {
+    fn print(&mut self, line: &str) {
+        (**self).print(line);
+    }
+    
+    fn chatter(&mut self, line: &str) {
+        (**self).chatter(line);
+    }
+}
+
+/// Keeps every printed line, for whoever wants to read them afterwards
+///
+/// Clones share the same lines, so keep one and hand the other to the interpreter.
+#[derive(Debug, Clone, Default)]
+pub struct BufferOutput {
+    lines: Rc<RefCell<Vec<String>>>,
+    chatter: Rc<RefCell<Vec<String>>>,
+}
+
+impl BufferOutput {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Everything the program printed so far
+    pub fn lines(&self) -> Vec<String> {
+        self.lines.borrow().clone()
+    }
+    
+    /// Everything the interpreter said on its own so far
+    pub fn chatter(&self) -> Vec<String> {
+        self.chatter.borrow().clone()
+    }
+}
+
+impl Output for BufferOutput {
+    fn print(&mut self, line: &str) {
+        self.lines.borrow_mut().push(line.to_string());
+    }
+    
+    fn chatter(&mut self, line: &str) {
+        self.chatter.borrow_mut().push(line.to_string());
+    }
+}
+
+/// Throws everything away. PUBU will never know
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NullOutput;
+
+impl Output for NullOutput {
+    fn print(&mut self, _line: &str) {}
+}
@@ -1,13 +1,19 @@
-use crate::ast::{Node, Operator, MoodType, Value};
-use crate::lexer::Token;
+use crate::ast::{Node, LoopKind, LunarCondition, Operator, Spanned, SpannedNode, DEFAULT_LOOP_VARIABLE};
+use crate::lexer::{Token, TokenType};
 use crate::mood::Mood;
 use crate::error::PubuError;
-use rand::Rng;
+use crate::lunar::Phase;
+use crate::span::Span;
+use crate::context::Context;
+use std::mem::discriminant;
 
 pub struct Parser {
     tokens: Vec<Token>,
     current: usize,
     current_mood: Mood,
+    mood_blocks: Vec<Mood>,
+    errors: Vec<PubuError>,
+    context: Context,
 }
 
 impl Parser {
@@ -16,10 +22,21 @@ impl Parser {
             tokens,
             current: 0,
             current_mood: mood,
+            mood_blocks: Vec::new(),
+            errors: Vec::new(),
+            context: Context::new(),
         }
     }
     
-    pub fn parse(&mut self) -> Result<Node, PubuError> {
+    /// Roll the parser's dice from `context`
+    pub fn with_context(mut self, context: Context) -> Self {
+        self.context = context;
+        self
+    }
+    
+    pub fn parse(&mut self) -> Result<SpannedNode, PubuError> {
+        let start = self.peek().span;
+        
         // PUBU requires compliments to run
         let mut has_compliment = false;
         
@@ -29,98 +46,650 @@ impl Parser {
             match self.parse_statement() {
                 Ok(node) => {
                     // Check if this statement is a compliment
-                    if let Node::Compliment(_) = node {
+                    if let Node::Compliment(_) = node.node {
                         has_compliment = true;
                     }
                     statements.push(node);
                 },
-                Err(err) => return Err(err),
+                Err(err) => {
+                    self.recover(err)?;
+                    // A stray `}` has no block left to close
+                    self.match_token(&TokenType::RightBrace);
+                },
             }
         }
         
+        // Report every syntax error found along the way, all at once
+        match self.errors.len() {
+            0 => {},
+            1 => return Err(self.errors.remove(0)),
+            _ => return Err(PubuError::SyntaxErrors(std::mem::take(&mut self.errors))),
+        }
+        
         // Ensure there's at least one compliment
         if !has_compliment {
             return Err(PubuError::NotEnoughCompliments);
         }
         
         // Random chance of failure regardless of correct syntax
-        if rand::thread_rng().gen_bool(0.05) {  // 5% chance
+        if self.context.chance(0.05) {  // 5% chance
             return Err(PubuError::RandomFailure);
         }
         
-        Ok(Node::Program(statements))
+        let span = start.to(self.peek().span);
+        Ok(Spanned::new(Node::Program(statements), span))
     }
     
-    fn parse_statement(&mut self) -> Result<Node, PubuError> {
+    fn parse_statement(&mut self) -> Result<SpannedNode, PubuError> {
         // Randomly have an existential crisis while parsing
-        if rand::thread_rng().gen_bool(0.02) {  // 2% chance
+        if self.context.chance(0.02) {  // 2% chance
             return Err(PubuError::PhilosophicalCrisis);
         }
         
-        // Parser logic would go here, detecting tokens and creating AST nodes
-        // This is a simplified placeholder version
+        let start = self.peek().span;
+        let node = match self.peek().token_type {
+            TokenType::Compliment => self.parse_compliment(),
+            TokenType::PhilosophicalQuestion => self.parse_philosophical_question(),
+            TokenType::MoodDeclaration => self.parse_mood_block(),
+            TokenType::LunarPhase => self.parse_lunar_phase_block(),
+            TokenType::RealityDistortion => self.parse_reality_distortion_block(),
+            TokenType::VariableDeclaration => self.parse_variable_declaration(),
+            TokenType::FunctionDeclaration => self.parse_function_declaration(),
+            TokenType::Conditional => self.parse_if_statement(),
+            TokenType::Loop => self.parse_loop(),
+            TokenType::Return => self.parse_return(),
+            TokenType::Break => self.parse_loop_control(Node::Break),
+            TokenType::Continue => self.parse_loop_control(Node::Continue),
+            TokenType::Identifier(_) => self.parse_identifier_statement(),
+            _ => Err(self.error("This does not look like the start of a statement", &[])),
+        }?;
         
-        // For now, just return a dummy node representing a compliment
-        // In a real implementation, this would examine tokens and create the appropriate nodes
-        Ok(Node::Compliment("dear_pubu_you_look_gorgeous_today".to_string()))
+        Ok(self.spanned(node, start))
     }
     
-    fn is_at_end(&self) -> bool {
-        self.current >= self.tokens.len()
+    fn parse_compliment(&mut self) -> Result<Node, PubuError> {
+        // A compliment may ramble on for as long as it likes before the `;`
+        let mut words = vec![self.advance().lexeme.clone()];
+        
+        while matches!(self.peek().token_type, TokenType::Identifier(_) | TokenType::String(_) | TokenType::Compliment) {
+            words.push(self.advance().lexeme.clone());
+        }
+        
+        self.consume(TokenType::Semicolon)?;
+        
+        Ok(Node::Compliment(words.join(" ")))
     }
     
-    // Helper methods for parsing different constructs would go here
-    
-    fn parse_compliment(&mut self) -> Result<Node, PubuError> {
-        // Check for compliment keywords
-        // In a real implementation, this would parse actual compliment syntax
-        Ok(Node::Compliment("Your implementation is beautiful".to_string()))
+    fn parse_philosophical_question(&mut self) -> Result<Node, PubuError> {
+        let question = self.advance().lexeme.clone();
+        
+        Ok(Node::PhilosophicalQuestion(question))
     }
     
     fn parse_mood_block(&mut self) -> Result<Node, PubuError> {
-        // Parse mood-specific blocks with their own syntax rules
-        // In a real implementation, this would handle the different mood-based keywords
-        
-        let mood_type = match self.current_mood {
-            Mood::Happy => MoodType::Happy,
-            Mood::Grumpy => MoodType::Grumpy,
-            Mood::Philosophical => MoodType::Philosophical,
-            Mood::Sarcastic => MoodType::Sarcastic,
-            Mood::Confused => MoodType::Confused,
+        // Parse mood-specific blocks, which bring their own vocabulary
+        let mood = Mood::from_name(&self.peek().lexeme)
+            .ok_or_else(|| self.error("Nobody has ever felt this way", &["a mood"]))?;
+        self.advance();
+        
+        match &self.peek().token_type {
+            TokenType::Identifier(name) if name == "mood" => {
+                self.advance();
+            },
+            _ => return Err(self.error("A mood needs to be declared as one", &["`mood`"])),
+        }
+        
+        self.mood_blocks.push(mood);
+        let body = self.parse_block();
+        self.mood_blocks.pop();
+        
+        Ok(Node::MoodBlock(mood.into(), body?))
+    }
+    
+    fn parse_lunar_phase_block(&mut self) -> Result<Node, PubuError> {
+        self.advance();
+        
+        let condition = if self.check(&TokenType::LeftBrace) {
+            LunarCondition::default()
+        } else if self.check_word("from") {
+            self.advance();
+            let from = self.consume_number()?;
+            if !self.check_word("to") {
+                return Err(self.error("The moon can't be lit from somewhere to nowhere", &["`to`"]));
+            }
+            self.advance();
+            let to = self.consume_number()?;
+            LunarCondition::Illumination { from, to }
+        } else {
+            let phase = match &self.peek().token_type {
+                TokenType::Identifier(name) => Phase::from_name(name),
+                _ => None,
+            };
+            match phase {
+                Some(phase) => {
+                    self.advance();
+                    LunarCondition::Phase(phase)
+                },
+                None => {
+                    let phases: Vec<String> = Phase::ALL.iter().map(|phase| format!("`{}`", phase.name())).collect();
+                    let expected: Vec<&str> = phases.iter().map(String::as_str).collect();
+                    return Err(self.error("The moon has no such phase", &expected));
+                },
+            }
+        };
+        
+        let body = self.parse_block()?;
+        
+        // `otherwise` only means something right after a lunar block
+        let next = self.tokens.get(self.current + 1).map(|token| &token.token_type);
+        let otherwise = if self.check_word("otherwise") && next == Some(&TokenType::LeftBrace) {
+            self.advance();
+            Some(self.parse_block()?)
+        } else {
+            None
         };
         
-        // Parse the block's body
-        let body = vec![]; // Placeholder
+        Ok(Node::LunarPhaseBlock(condition, body, otherwise))
+    }
+    
+    fn parse_reality_distortion_block(&mut self) -> Result<Node, PubuError> {
+        self.advance();
+        let start = self.peek().span;
+        let body = self.parse_block()?;
+        let block = self.spanned(Node::Block(body), start);
         
-        Ok(Node::MoodBlock(mood_type, body))
+        Ok(Node::RealityDistortion(Box::new(block)))
     }
     
-    fn parse_philosophical_question(&mut self) -> Result<Node, PubuError> {
-        // Parse a philosophical question required in certain contexts
-        // In a real implementation, this would look for question syntax
+    fn parse_variable_declaration(&mut self) -> Result<Node, PubuError> {
+        self.advance();
+        let name = self.consume_identifier()?;
+        self.consume_assignment()?;
+        let value = self.parse_expression()?;
+        self.consume(TokenType::Semicolon)?;
         
-        Ok(Node::PhilosophicalQuestion("Why do we code when code is ephemeral?".to_string()))
+        Ok(Node::VariableDeclaration(name, Box::new(value)))
     }
     
-    fn parse_lunar_phase_block(&mut self) -> Result<Node, PubuError> {
-        // Parse time-sensitive code blocks
-        // In a real implementation, this would look for lunar_phase syntax
+    fn parse_function_declaration(&mut self) -> Result<Node, PubuError> {
+        self.advance();
+        let name = self.consume_identifier()?;
+        self.consume(TokenType::LeftParen)?;
+        
+        let mut params = Vec::new();
+        if !self.check(&TokenType::RightParen) {
+            loop {
+                params.push(self.consume_identifier()?);
+                if !self.match_token(&TokenType::Comma) {
+                    break;
+                }
+            }
+        }
+        
+        self.consume(TokenType::RightParen)?;
+        let body = self.parse_block()?;
+        
+        Ok(Node::FunctionDeclaration(name, params, body.into()))
+    }
+    
+    fn parse_if_statement(&mut self) -> Result<Node, PubuError> {
+        self.advance();
+        let condition = self.parse_expression()?;
+        let then_branch = self.parse_block()?;
+        
+        let else_branch = if !self.match_token(&TokenType::Else) {
+            None
+        } else if self.check(&TokenType::Conditional) {
+            // `else` followed by another conditional chains a whole new if statement
+            let start = self.peek().span;
+            let chained = self.parse_if_statement()?;
+            Some(vec![self.spanned(chained, start)])
+        } else {
+            Some(self.parse_block()?)
+        };
+        
+        Ok(Node::IfStatement(Box::new(condition), then_branch, else_branch))
+    }
+    
+    fn parse_loop(&mut self) -> Result<Node, PubuError> {
+        self.advance();
+        
+        // A range loop can name its count before `from`
+        let next = self.tokens.get(self.current + 1).map(|token| token.lexeme.as_str());
+        let variable = match &self.peek().token_type {
+            TokenType::Identifier(name) if next == Some("from") => {
+                let name = name.clone();
+                self.advance();
+                Some(name)
+            },
+            _ => None,
+        };
+        
+        let kind = if self.check_word("from") {
+            self.advance();
+            let from = self.parse_expression()?;
+            if !self.check_word("to") {
+                return Err(self.error("A loop that starts somewhere has to end somewhere", &["`to`"]));
+            }
+            self.advance();
+            let to = self.parse_expression()?;
+            LoopKind::Range {
+                variable: variable.unwrap_or_else(|| DEFAULT_LOOP_VARIABLE.to_string()),
+                from: Box::new(from),
+                to: Box::new(to),
+            }
+        } else if !self.check(&TokenType::LeftBrace) {
+            let count = self.parse_expression()?;
+            if self.at_loop_marker() {
+                self.advance();
+            }
+            LoopKind::Counted(Box::new(count))
+        } else {
+            LoopKind::Infinite
+        };
+        
+        let body = self.parse_block()?;
+        
+        Ok(Node::Loop(kind, body))
+    }
+    
+    fn parse_loop_control(&mut self, node: Node) -> Result<Node, PubuError> {
+        self.advance();
+        self.consume(TokenType::Semicolon)?;
+        
+        Ok(node)
+    }
+    
+    fn parse_return(&mut self) -> Result<Node, PubuError> {
+        self.advance();
+        
+        let value = if self.check(&TokenType::Semicolon) {
+            None
+        } else {
+            Some(Box::new(self.parse_expression()?))
+        };
+        
+        self.consume(TokenType::Semicolon)?;
+        
+        Ok(Node::Return(value))
+    }
+    
+    fn parse_identifier_statement(&mut self) -> Result<Node, PubuError> {
+        // `dear_pubu_you_look_gorgeous_today;` is a compliment, while
+        // `gorgeous_count is 3;` is still just an assignment
+        let is_compliment = match &self.peek().token_type {
+            TokenType::Identifier(name) => crate::is_compliment(name),
+            _ => false,
+        };
+        let next = self.tokens.get(self.current + 1).map(|token| &token.token_type);
+        if is_compliment && matches!(next, Some(TokenType::Semicolon | TokenType::Identifier(_) | TokenType::String(_) | TokenType::Compliment)) {
+            return self.parse_compliment();
+        }
+        
+        let name = self.consume_identifier()?;
+        
+        // `print` is not a keyword, so it only means printing right before a `(`
+        if name == "print" && self.check(&TokenType::LeftParen) {
+            return self.parse_print();
+        }
         
-        let body = vec![]; // Placeholder
+        if self.check(&TokenType::LeftParen) {
+            let call = self.finish_call(name)?;
+            self.consume(TokenType::Semicolon)?;
+            return Ok(call);
+        }
+        
+        self.consume_assignment()?;
+        let value = self.parse_expression()?;
+        self.consume(TokenType::Semicolon)?;
+        
+        Ok(Node::Assignment(name, Box::new(value)))
+    }
+    
+    fn parse_print(&mut self) -> Result<Node, PubuError> {
+        self.consume(TokenType::LeftParen)?;
+        let value = self.parse_expression()?;
+        self.consume(TokenType::RightParen)?;
+        self.consume(TokenType::Semicolon)?;
+        
+        Ok(Node::Print(Box::new(value)))
+    }
+    
+    fn parse_block(&mut self) -> Result<Vec<SpannedNode>, PubuError> {
+        self.consume(TokenType::LeftBrace)?;
+        
+        let mut statements = Vec::new();
+        while !self.check(&TokenType::RightBrace) && !self.is_at_end() {
+            match self.parse_statement() {
+                Ok(statement) => statements.push(statement),
+                Err(err) => self.recover(err)?,
+            }
+        }
+        
+        self.consume(TokenType::RightBrace)?;
         
-        Ok(Node::LunarPhaseBlock(body))
+        Ok(statements)
     }
     
-    fn parse_expression(&mut self) -> Result<Node, PubuError> {
-        // Basic expression parsing
-        // In a real implementation, this would handle variables, literals, operations, etc.
+    fn parse_expression(&mut self) -> Result<SpannedNode, PubuError> {
+        let expr = self.parse_or()?;
         
         // Randomly distort reality while parsing expressions
-        if rand::thread_rng().gen_bool(0.1) {  // 10% chance
-            let expr = Node::Number(42.0); // Placeholder
-            return Ok(Node::RealityDistortion(Box::new(expr)));
+        if self.context.chance(0.1) {  // 10% chance
+            let span = expr.span;
+            return Ok(Spanned::new(Node::RealityDistortion(Box::new(expr)), span));
+        }
+        
+        Ok(expr)
+    }
+    
+    fn parse_or(&mut self) -> Result<SpannedNode, PubuError> {
+        let mut expr = self.parse_and()?;
+        
+        while let Some(op) = self.match_operator(&[(TokenType::Or, Operator::Or)]) {
+            let right = self.parse_and()?;
+            expr = Self::binary(expr, op, right);
+        }
+        
+        Ok(expr)
+    }
+    
+    fn parse_and(&mut self) -> Result<SpannedNode, PubuError> {
+        let mut expr = self.parse_equality()?;
+        
+        while let Some(op) = self.match_operator(&[(TokenType::And, Operator::And)]) {
+            let right = self.parse_equality()?;
+            expr = Self::binary(expr, op, right);
         }
         
-        Ok(Node::Number(0.0)) // Placeholder
+        Ok(expr)
     }
-} 
\ No newline at end of file
+    
+    fn parse_equality(&mut self) -> Result<SpannedNode, PubuError> {
+        let mut expr = self.parse_comparison()?;
+        
+        while let Some(op) = self.match_operator(&[
+            (TokenType::Equal, Operator::Equal),
+            (TokenType::NotEqual, Operator::NotEqual),
+        ]) {
+            let right = self.parse_comparison()?;
+            expr = Self::binary(expr, op, right);
+        }
+        
+        Ok(expr)
+    }
+    
+    fn parse_comparison(&mut self) -> Result<SpannedNode, PubuError> {
+        let mut expr = self.parse_term()?;
+        
+        while let Some(op) = self.match_operator(&[
+            (TokenType::GreaterThan, Operator::GreaterThan),
+            (TokenType::LessThan, Operator::LessThan),
+        ]) {
+            let right = self.parse_term()?;
+            expr = Self::binary(expr, op, right);
+        }
+        
+        Ok(expr)
+    }
+    
+    fn parse_term(&mut self) -> Result<SpannedNode, PubuError> {
+        let mut expr = self.parse_factor()?;
+        
+        while let Some(op) = self.match_operator(&[
+            (TokenType::Plus, Operator::Add),
+            (TokenType::Minus, Operator::Subtract),
+        ]) {
+            let right = self.parse_factor()?;
+            expr = Self::binary(expr, op, right);
+        }
+        
+        Ok(expr)
+    }
+    
+    fn parse_factor(&mut self) -> Result<SpannedNode, PubuError> {
+        let mut expr = self.parse_unary()?;
+        
+        while !self.at_loop_marker() {
+            let Some(op) = self.match_operator(&[
+                (TokenType::Times, Operator::Multiply),
+                (TokenType::Divide, Operator::Divide),
+            ]) else {
+                break;
+            };
+            let right = self.parse_unary()?;
+            expr = Self::binary(expr, op, right);
+        }
+        
+        Ok(expr)
+    }
+    
+    fn parse_unary(&mut self) -> Result<SpannedNode, PubuError> {
+        let start = self.peek().span;
+        
+        if let Some(op) = self.match_operator(&[
+            (TokenType::Minus, Operator::Subtract),
+            (TokenType::Not, Operator::Not),
+        ]) {
+            let operand = self.parse_unary()?;
+            let span = start.to(operand.span);
+            return Ok(Spanned::new(Node::UnaryOp(op, Box::new(operand)), span));
+        }
+        
+        self.parse_primary()
+    }
+    
+    fn parse_primary(&mut self) -> Result<SpannedNode, PubuError> {
+        let token = self.peek().clone();
+        
+        // Anything that isn't a value stays put, so error recovery can see it
+        if !matches!(
+            token.token_type,
+            TokenType::Number(_) | TokenType::String(_) | TokenType::Boolean(_)
+                | TokenType::Identifier(_) | TokenType::LeftParen
+        ) {
+            return Err(self.error("This is not something PUBU can compute", &["a value"]));
+        }
+        self.advance();
+        
+        let node = match token.token_type {
+            TokenType::Number(n) => Node::Number(n),
+            TokenType::String(s) => Node::String(s),
+            TokenType::Boolean(b) => Node::Boolean(b),
+            TokenType::Identifier(name) => {
+                if self.check(&TokenType::LeftParen) {
+                    self.finish_call(name)?
+                } else {
+                    Node::Identifier(name)
+                }
+            },
+            _ => {
+                let expr = self.parse_expression()?;
+                self.consume(TokenType::RightParen)?;
+                expr.node
+            },
+        };
+        
+        Ok(self.spanned(node, token.span))
+    }
+    
+    fn finish_call(&mut self, name: String) -> Result<Node, PubuError> {
+        self.consume(TokenType::LeftParen)?;
+        
+        let mut args = Vec::new();
+        if !self.check(&TokenType::RightParen) {
+            loop {
+                args.push(self.parse_expression()?);
+                if !self.match_token(&TokenType::Comma) {
+                    break;
+                }
+            }
+        }
+        
+        self.consume(TokenType::RightParen)?;
+        
+        Ok(Node::FunctionCall(name, args))
+    }
+    
+    /// Remember a syntax error and skip ahead to where the next statement
+    /// probably starts. Any other error is PUBU's mood and stops everything.
+    fn recover(&mut self, err: PubuError) -> Result<(), PubuError> {
+        if !matches!(err, PubuError::SyntaxError { .. }) {
+            return Err(err);
+        }
+        self.errors.push(err);
+        
+        // Skip to just after the next `;`, or to the `}` that closes the
+        // current block, jumping over any blocks the broken statement opened
+        let mut depth = 0;
+        while !self.is_at_end() {
+            match self.peek().token_type {
+                TokenType::Semicolon if depth == 0 => {
+                    self.advance();
+                    return Ok(());
+                },
+                TokenType::RightBrace if depth == 0 => return Ok(()),
+                TokenType::RightBrace => {
+                    depth -= 1;
+                    self.advance();
+                    if depth == 0 {
+                        return Ok(());
+                    }
+                    continue;
+                },
+                TokenType::LeftBrace => depth += 1,
+                _ => {},
+            }
+            self.advance();
+        }
+        
+        Ok(())
+    }
+    
+    // Helper methods for walking the token stream
+    
+    /// Attaches the span from `start` to the last consumed token
+    fn spanned(&self, node: Node, start: Span) -> SpannedNode {
+        let end = self.tokens[self.current.saturating_sub(1)].span;
+        Spanned::new(node, start.to(end))
+    }
+    
+    /// A syntax error about the token PUBU is looking at
+    fn error(&self, message: &str, expected: &[&str]) -> PubuError {
+        let token = self.peek();
+        PubuError::SyntaxError {
+            span: token.span,
+            message: message.to_string(),
+            expected: expected.iter().map(|e| e.to_string()).collect(),
+            found: token.describe(),
+        }
+    }
+    
+    fn binary(left: SpannedNode, op: Operator, right: SpannedNode) -> SpannedNode {
+        let span = left.span.to(right.span);
+        Spanned::new(Node::BinaryOp(Box::new(left), op, Box::new(right)), span)
+    }
+    
+    fn active_mood(&self) -> Mood {
+        self.mood_blocks.last().copied().unwrap_or(self.current_mood)
+    }
+    
+    fn is_at_end(&self) -> bool {
+        self.peek().token_type == TokenType::EOF
+    }
+    
+    fn peek(&self) -> &Token {
+        // The lexer always ends the stream with EOF, so there is always a last token
+        let index = self.current.min(self.tokens.len().saturating_sub(1));
+        &self.tokens[index]
+    }
+    
+    fn advance(&mut self) -> &Token {
+        if !self.is_at_end() {
+            self.current += 1;
+        }
+        &self.tokens[self.current - 1]
+    }
+    
+    fn check(&self, token_type: &TokenType) -> bool {
+        discriminant(&self.peek().token_type) == discriminant(token_type)
+    }
+    
+    /// Check for an identifier-like word that is only a keyword in context,
+    /// such as `from`, `to` or `times` in a loop header
+    fn check_word(&self, word: &str) -> bool {
+        self.peek().lexeme == word && !matches!(self.peek().token_type, TokenType::String(_))
+    }
+    
+    /// `times` is Happy PUBU's multiplication operator, but right before a
+    /// `{` it always ends a loop header instead, so `again_and_again n times { }`
+    /// repeats `n` times while `again_and_again n times m { }` repeats `n * m` times
+    fn at_loop_marker(&self) -> bool {
+        let next = self.tokens.get(self.current + 1).map(|token| &token.token_type);
+        self.check_word("times") && next == Some(&TokenType::LeftBrace)
+    }
+    
+    fn match_token(&mut self, token_type: &TokenType) -> bool {
+        if self.check(token_type) {
+            self.advance();
+            true
+        } else {
+            false
+        }
+    }
+    
+    fn match_operator(&mut self, operators: &[(TokenType, Operator)]) -> Option<Operator> {
+        let op = operators.iter()
+            .find(|(token_type, _)| self.check(token_type))
+            .map(|(_, op)| *op)?;
+        
+        self.advance();
+        Some(op)
+    }
+    
+    fn consume(&mut self, token_type: TokenType) -> Result<&Token, PubuError> {
+        if self.check(&token_type) {
+            Ok(self.advance())
+        } else {
+            Err(self.error("Something is missing here", &[&token_type.describe()]))
+        }
+    }
+    
+    fn consume_number(&mut self) -> Result<f64, PubuError> {
+        match self.peek().token_type {
+            TokenType::Number(n) => {
+                self.advance();
+                Ok(n)
+            },
+            _ => Err(self.error("PUBU needs a number here", &["a number"])),
+        }
+    }
+    
+    fn consume_identifier(&mut self) -> Result<String, PubuError> {
+        match &self.peek().token_type {
+            TokenType::Identifier(name) => {
+                let name = name.clone();
+                self.advance();
+                Ok(name)
+            },
+            _ => Err(self.error("This needs a name", &["a name"])),
+        }
+    }
+    
+    fn consume_assignment(&mut self) -> Result<(), PubuError> {
+        // Grumpy PUBU uses `equals` both to assign and to compare, and the
+        // lexer always reads it as the comparison
+        let assignment = self.active_mood().get_keywords().assignment;
+        let token = self.peek();
+        
+        let is_assignment = token.token_type == TokenType::Assignment
+            || (token.token_type == TokenType::Equal && token.lexeme == assignment);
+        
+        if is_assignment {
+            self.advance();
+            Ok(())
+        } else {
+            Err(self.error("PUBU expected to be told what this is", &[&format!("`{}`", assignment)]))
+        }
+    }
+}
@@ -1,9 +1,10 @@
-use std::time::{SystemTime, UNIX_EPOCH};
 use rand::Rng;
 use colored::*;
+use crate::context::Context;
+use crate::clock::Clock;
 
 /// Generate an absurdly complicated philosophical error message
-pub fn generate_philosophical_error(message: &str) -> String {
+pub fn generate_philosophical_error(message: &str, context: &Context) -> String {
     let quotes = [
         "To err is human, to debug divine.",
         "The code not taken makes all the difference.",
@@ -15,7 +16,7 @@ pub fn generate_philosophical_error(message: &str) -> String {
         "Bug or feature? That is the question.",
     ];
     
-    let quote = quotes[rand::thread_rng().gen_range(0..quotes.len())];
+    let quote = quotes[context.rng().gen_range(0..quotes.len())];
     
     format!(
         "{}\n{}\n{}",
@@ -26,7 +27,7 @@ pub fn generate_philosophical_error(message: &str) -> String {
 }
 
 /// Generate a random fact that has nothing to do with the code
-pub fn generate_random_fact() -> String {
+pub fn generate_random_fact(context: &Context) -> String {
     let facts = [
         "Bananas are berries, but strawberries are not.",
         "A day on Venus is longer than a year on Venus.",
@@ -38,29 +39,20 @@ pub fn generate_random_fact() -> String {
         "The fingerprints of koalas are so similar to humans that they have on occasion been confused at crime scenes.",
     ];
     
-    let fact = facts[rand::thread_rng().gen_range(0..facts.len())];
+    let fact = facts[context.rng().gen_range(0..facts.len())];
     
     format!("Random Fact: {}", fact.blue())
 }
 
-/// Determines if the current time is right for a lunar phase operation
-pub fn is_lunar_phase_compatible() -> bool {
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_secs() / 86400; // Days since epoch
-    
-    // Simplified moon phase calculation (completely unscientific)
-    let moon_phase = now % 30;
-    
-    // Only works during "full moon" (days 13-17 of our simplified cycle)
-    moon_phase >= 13 && moon_phase <= 17
+/// Determines if the time `clock` shows is right for a lunar phase operation
+pub fn is_lunar_phase_compatible(clock: &dyn Clock) -> bool {
+    crate::is_lunar_compatible(clock)
 }
 
 /// Simulate AI-powered code critique with a random delay
-pub fn ai_powered_critique(code: &str) -> String {
+pub fn ai_powered_critique(_code: &str, context: &Context) -> String {
     // Add a deliberate delay to make it feel like "AI processing"
-    let delay = rand::thread_rng().gen_range(500..2000);
+    let delay = context.rng().gen_range(500..2000);
     std::thread::sleep(std::time::Duration::from_millis(delay));
     
     let critiques = [
@@ -81,8 +73,8 @@ pub fn ai_powered_critique(code: &str) -> String {
         "4/10 - Marginally acceptable. Consider a career in interpretive dance instead.",
     ];
     
-    let critique = critiques[rand::thread_rng().gen_range(0..critiques.len())];
-    let rating = ratings[rand::thread_rng().gen_range(0..ratings.len())];
+    let critique = critiques[context.rng().gen_range(0..critiques.len())];
+    let rating = ratings[context.rng().gen_range(0..ratings.len())];
     
     format!(
         "{}\n{}\n{}",
@@ -99,7 +91,7 @@ pub fn apply_reality_distortion<T: std::fmt::Display>(value: T) -> String {
 }
 
 /// Generate encouraging yet useless debugging tips
-pub fn generate_debugging_tip() -> String {
+pub fn generate_debugging_tip(context: &Context) -> String {
     let tips = [
         "Have you tried turning it off and on again?",
         "Consider adding more compliments to your code.",
@@ -111,7 +103,7 @@ pub fn generate_debugging_tip() -> String {
         "The error is probably on line 42. It's always line 42.",
     ];
     
-    let tip = tips[rand::thread_rng().gen_range(0..tips.len())];
+    let tip = tips[context.rng().gen_range(0..tips.len())];
     
     format!("Debugging Tip: {}", tip.green())
 } 
\ No newline at end of file
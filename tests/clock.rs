@@ -0,0 +1,80 @@
+use pubu::ast::Value;
+use pubu::clock::{Clock, FixedClock, OffsetClock, SystemClock};
+use pubu::context::Context;
+use pubu::output::NullOutput;
+use pubu::error::PubuError;
+use pubu::mood::Mood;
+use pubu::{evaluate_in, get_current_mood, is_lunar_compatible, utils, Frontend};
+use std::time::Duration;
+
+mod common;
+use common::FULL_MOON;
+
+const DAY: u64 = 86400;
+/// 2024-04-08 18:21 UTC, the day of the total solar eclipse
+const NEW_MOON: u64 = 1_712_600_460;
+/// 2024-04-15 19:13 UTC
+const FIRST_QUARTER: u64 = 1_713_208_380;
+
+#[test]
+fn the_mood_depends_only_on_the_clock() {
+    let moods: Vec<Mood> = (0..5)
+        .map(|second| get_current_mood(&FixedClock::at_unix_seconds(1_000_000_000 + second)))
+        .collect();
+
+    assert_eq!(moods, Mood::ALL);
+    assert_eq!(get_current_mood(&FixedClock::at_unix_seconds(5)), get_current_mood(&FixedClock::at_unix_seconds(0)));
+}
+
+#[test]
+fn both_lunar_checks_read_the_same_clock() {
+    for (timestamp, full) in [(FULL_MOON, true), (NEW_MOON, false), (FULL_MOON + 7 * DAY, false)] {
+        let clock = FixedClock::at_unix_seconds(timestamp);
+        assert_eq!(is_lunar_compatible(&clock), full, "at {}", timestamp);
+        assert_eq!(utils::is_lunar_phase_compatible(&clock), full, "at {}", timestamp);
+    }
+}
+
+#[test]
+fn offset_clocks_keep_their_distance() {
+    let ahead = OffsetClock::ahead(Duration::from_secs(DAY)).unix_seconds();
+    let behind = OffsetClock::behind(Duration::from_secs(DAY)).unix_seconds();
+    let now = SystemClock.unix_seconds();
+
+    assert!((ahead - now).abs_diff(DAY) <= 1, "{} is not a day after {}", ahead, now);
+    assert!((now - behind).abs_diff(DAY) <= 1, "{} is not a day before {}", behind, now);
+}
+
+/// Evaluates `source` as of `timestamp`, without PUBU's whims getting in the way
+fn evaluate_at(source: &str, timestamp: u64) -> Result<Value, PubuError> {
+    let context = Context::calm().with_clock(FixedClock::at_unix_seconds(timestamp));
+    evaluate_in(source, Mood::Happy, Frontend::Pest, &context, &mut NullOutput)
+}
+
+#[test]
+fn lunar_blocks_only_run_when_the_clock_says_so() {
+    let source = "dear_pubu;\nlunar_phase {\n  yay x is 1;\n}";
+
+    assert!(evaluate_at(source, FULL_MOON).is_ok());
+    assert!(matches!(evaluate_at(source, NEW_MOON).unwrap_err().inner(), PubuError::MoonPhaseIncompatible));
+}
+
+#[test]
+fn lunar_blocks_wait_for_their_own_moon_or_fall_back() {
+    let source = "dear_pubu;\nyay x is 0;\nlunar_phase full {\n  x is 1;\n} otherwise {\n  x is 2;\n}\nlunar_phase from 0.4 to 0.6 {\n  x is x plus 10;\n} otherwise {\n}\nyay y is x;";
+    for (timestamp, expected) in [(FULL_MOON, 1.0), (NEW_MOON, 2.0), (FIRST_QUARTER, 12.0)] {
+        let result = evaluate_at(source, timestamp);
+        assert!(matches!(result, Ok(Value::Number(n)) if n == expected), "{:?} at {}", result, timestamp);
+    }
+}
+
+#[test]
+fn the_sky_can_be_read_like_a_variable() {
+    let phase = "dear_pubu;\nyay x is moon_phase;";
+    let result = evaluate_at(phase, NEW_MOON);
+    assert!(matches!(&result, Ok(Value::String(s)) if s == "new"), "{:?}", result);
+
+    let illumination = "dear_pubu;\nyay x is moon_illumination;";
+    let result = evaluate_at(illumination, FULL_MOON);
+    assert!(matches!(result, Ok(Value::Number(n)) if n > 0.99), "{:?}", result);
+}
@@ -0,0 +1,9 @@
+//! Fixtures shared by the integration tests, each of which uses only some of them
+#![allow(dead_code)]
+
+use pubu::Frontend;
+
+pub const FRONTENDS: [Frontend; 2] = [Frontend::HandWritten, Frontend::Pest];
+
+/// 2024-04-23 23:49 UTC
+pub const FULL_MOON: u64 = 1_713_916_140;
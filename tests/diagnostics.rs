@@ -0,0 +1,126 @@
+use pubu::context::Context;
+use pubu::error::PubuError;
+use pubu::mood::Mood;
+use pubu::span::Span;
+use pubu::{parse_in, Frontend};
+
+mod common;
+use common::FRONTENDS;
+
+fn syntax_error(source: &str, frontend: Frontend) -> PubuError {
+    match parse_in(source, Mood::Happy, frontend, &Context::calm()) {
+        Err(err) => err,
+        Ok(program) => panic!("{:?} accepted {:?} as {:?}", frontend, source, program),
+    }
+}
+
+#[test]
+fn syntax_errors_point_at_the_offending_token() {
+    colored::control::set_override(false);
+    let source = "dear_pubu;\nyay x is 3;\nyay y is (x plus ;\n";
+
+    for frontend in FRONTENDS {
+        let err = syntax_error(source, frontend);
+        match &err {
+            PubuError::SyntaxError { span, expected, found, .. } => {
+                assert_eq!((span.line, span.column), (3, 18), "{:?}", frontend);
+                assert!(!expected.is_empty(), "{:?} expected nothing", frontend);
+                assert_eq!(found, "`;`");
+            },
+            other => panic!("{:?} failed with {:?}", frontend, other),
+        }
+
+        let rendered = err.render(source, Mood::Philosophical, &Context::calm());
+        assert!(rendered.contains("3 | yay y is (x plus ;\n  |                  ^\n"), "{:?} rendered\n{}", frontend, rendered);
+    }
+}
+
+#[test]
+fn runtime_errors_keep_the_innermost_location() {
+    let statement = Span::new(11, 30, 2, 1);
+    let expression = Span::new(20, 25, 2, 10);
+
+    let err = PubuError::BadMood.at(expression).at(statement);
+    assert_eq!(err.span(), Some(expression));
+    assert!(matches!(err, PubuError::Located { error, .. } if matches!(*error, PubuError::BadMood)));
+}
+
+#[test]
+fn every_mood_complains_in_its_own_tone() {
+    colored::control::set_override(false);
+    let span = Span::new(0, 9, 1, 1);
+    let errors = [
+        PubuError::NotEnoughCompliments,
+        PubuError::BadMood,
+        PubuError::MoonPhaseIncompatible,
+        PubuError::RandomFailure,
+        PubuError::PhilosophicalCrisis,
+        PubuError::AiCriticismFailure,
+        PubuError::VariableRealityDistortion,
+        PubuError::UndefinedVariable("x".to_string()),
+        PubuError::UndeclaredAssignment("x".to_string()),
+        PubuError::UndefinedFunction("f".to_string()),
+        PubuError::NotCallable { name: "f".to_string(), kind: "a number" },
+        PubuError::ArityMismatch { name: "f".to_string(), expected: 1, found: 2 },
+        PubuError::RecursionTooDeep { name: "f".to_string(), depth: 16 },
+        PubuError::OutsideLoop,
+        PubuError::TypeMismatch { operator: "+".to_string(), left: "a number", right: Some("a boolean") },
+        PubuError::SyntaxError { span, message: "Oops".to_string(), expected: vec!["`;`".to_string()], found: "`}`".to_string() },
+        PubuError::RandomFailure.at(span),
+    ];
+
+    for err in errors {
+        // Everything but the closing quote, which is chosen at random
+        let mut complaints: Vec<String> = Mood::ALL.iter()
+            .map(|mood| {
+                let rendered = err.render("dear_pubu;", *mood, &Context::calm());
+                rendered.lines().take(2).collect::<Vec<_>>().join("\n")
+            })
+            .collect();
+        complaints.sort();
+        complaints.dedup();
+        assert_eq!(complaints.len(), Mood::ALL.len(), "{:?} sounds the same in some moods: {:?}", err, complaints);
+    }
+}
+
+#[test]
+fn displaying_an_error_needs_neither_clock_nor_dice() {
+    colored::control::set_override(false);
+
+    for err in [PubuError::RandomFailure, PubuError::UndefinedVariable("x".to_string())] {
+        let displayed = err.to_string();
+        assert!((0..20).all(|_| err.to_string() == displayed), "{:?} keeps changing its mind", err);
+
+        // Errors that nobody renders speak in philosophical mood, whatever the time
+        let (displayed_complaint, _) = displayed.rsplit_once('\n').unwrap();
+        let rendered = err.render("dear_pubu;", Mood::Philosophical, &Context::calm());
+        let (rendered_complaint, _) = rendered.rsplit_once('\n').unwrap();
+        assert_eq!(displayed_complaint, rendered_complaint);
+    }
+}
+
+#[test]
+fn the_parser_reports_every_syntax_error_at_once() {
+    let source = "dear_pubu;\nyay x is 3 plus;\nperhaps x bigger_than { yay z is ; }\nyay w 4;\nyay ok is 1;\n";
+
+    match syntax_error(source, Frontend::HandWritten) {
+        PubuError::SyntaxErrors(errors) => {
+            let lines: Vec<usize> = errors.iter()
+                .map(|err| err.span().expect("syntax errors know where they are").line)
+                .collect();
+            assert_eq!(lines, [2, 3, 4]);
+        },
+        other => panic!("expected several syntax errors, got {:?}", other),
+    }
+}
+
+#[test]
+fn checking_a_file_without_chaos_never_fails_at_random() {
+    let source = "dear_pubu;\nyay x is 1;\nwhy?\nperhaps x bigger_than 0 {\n  x is x plus 1;\n}";
+
+    for seed in 0..50 {
+        let context = Context::seeded(seed).without_chaos();
+        let result = parse_in(source, Mood::Happy, Frontend::HandWritten, &context);
+        assert!(result.is_ok(), "seed {}: {:?}", seed, result);
+    }
+}
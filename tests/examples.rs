@@ -0,0 +1,70 @@
+//! The shipped examples double as a regression suite for both front ends
+//! and the interpreter
+
+use pubu::ast::{Node, SpannedNode};
+use pubu::clock::FixedClock;
+use pubu::context::Context;
+use pubu::output::NullOutput;
+use pubu::mood::Mood;
+use pubu::{evaluate_in, parse, parse_in, Frontend};
+
+mod common;
+use common::{FRONTENDS, FULL_MOON};
+
+/// Each example with the mood its top-level code is written in
+const EXAMPLES: [(&str, Mood); 4] = [
+    ("hello_world", Mood::Happy),
+    ("grumpy_calculator", Mood::Grumpy),
+    ("philosophical_fibonacci", Mood::Philosophical),
+    ("pubu_showcase", Mood::Happy),
+];
+
+fn example_source(name: &str) -> String {
+    let path = format!("{}/examples/{}.pubu", env!("CARGO_MANIFEST_DIR"), name);
+    std::fs::read_to_string(&path).unwrap_or_else(|err| panic!("cannot read {}: {}", path, err))
+}
+
+
+fn count_compliments(statements: &[SpannedNode]) -> usize {
+    statements.iter()
+        .filter(|statement| matches!(statement.node, Node::Compliment(_)))
+        .count()
+}
+
+#[test]
+fn examples_parse_with_pest() {
+    for (name, mood) in EXAMPLES {
+        let program = parse(&example_source(name), mood, Frontend::Pest)
+            .unwrap_or_else(|err| panic!("{} failed to parse: {:?}", name, err));
+
+        match program.node {
+            Node::Program(statements) => assert!(count_compliments(&statements) >= 2, "{} lost its compliments", name),
+            other => panic!("{} parsed to {:?}", name, other),
+        }
+    }
+}
+
+#[test]
+fn examples_parse_with_hand_written_parser() {
+    for (name, mood) in EXAMPLES {
+        let program = parse_in(&example_source(name), mood, Frontend::HandWritten, &Context::calm())
+            .unwrap_or_else(|err| panic!("{} failed to parse: {:?}", name, err));
+
+        match program.node {
+            Node::Program(statements) => assert!(count_compliments(&statements) >= 2, "{} lost its compliments", name),
+            other => panic!("{} parsed to {:?}", name, other),
+        }
+    }
+}
+
+#[test]
+fn examples_run_under_a_full_moon() {
+    for frontend in FRONTENDS {
+        for (name, mood) in EXAMPLES {
+            // The full moon lets the examples' lunar blocks run
+            let context = Context::calm().with_clock(FixedClock::at_unix_seconds(FULL_MOON));
+            let result = evaluate_in(&example_source(name), mood, frontend, &context, &mut NullOutput);
+            assert!(result.is_ok(), "{} failed to run with {:?}: {:?}", name, frontend, result);
+        }
+    }
+}
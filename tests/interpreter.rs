@@ -0,0 +1,349 @@
+use pubu::ast::{Environment, Function, Operator, Value};
+use pubu::error::PubuError;
+use pubu::interpreter::{Interpreter, DEFAULT_MAX_CALL_DEPTH};
+use pubu::mood::Mood;
+use pubu::output::{BufferOutput, NullOutput};
+use pubu::context::Context;
+use pubu::{evaluate_in, parse, Frontend};
+
+/// Evaluates `source` in happy mood, without PUBU's whims getting in the way
+fn run(source: &str) -> Result<Value, PubuError> {
+    evaluate_in(source, Mood::Happy, Frontend::HandWritten, &Context::calm(), &mut NullOutput)
+}
+
+fn assert_evaluates_to(source: &str, expected: f64) {
+    let result = run(source);
+    assert!(matches!(result, Ok(Value::Number(n)) if n == expected), "{:?} instead of {}", result, expected);
+}
+
+#[test]
+fn variables_can_be_declared_assigned_and_read() {
+    assert_evaluates_to("dear_pubu;\nyay x is 1;\nx is 2;\nyay y is x;", 2.0);
+}
+
+#[test]
+fn reading_an_undefined_variable_fails_where_it_is_read() {
+    let source = "dear_pubu;\nyay x is y;";
+    let err = run(source).expect_err("y was never declared");
+
+    assert!(matches!(err.inner(), PubuError::UndefinedVariable(name) if name == "y"), "{:?}", err);
+    let span = err.span().expect("runtime errors know where they happened");
+    assert_eq!(&source[span.start..span.end], "y");
+}
+
+#[test]
+fn assigning_an_undeclared_variable_fails() {
+    let err = run("dear_pubu;\nx is 2;").expect_err("x was never declared");
+
+    assert!(matches!(err.inner(), PubuError::UndeclaredAssignment(name) if name == "x"), "{:?}", err);
+}
+
+fn number(n: f64) -> Value {
+    Value::Number(n)
+}
+
+fn string(s: &str) -> Value {
+    Value::String(s.to_string())
+}
+
+#[test]
+fn numbers_do_arithmetic_and_strings_concatenate() {
+    assert!(matches!(number(5.0).binary(Operator::Add, &number(7.0)), Ok(Value::Number(n)) if n == 12.0));
+    assert!(matches!(number(4.0).binary(Operator::Multiply, &number(6.0)), Ok(Value::Number(n)) if n == 24.0));
+    assert!(matches!(number(3.0).unary(Operator::Subtract), Ok(Value::Number(n)) if n == -3.0));
+
+    let greeting = string("Happy result: ").binary(Operator::Add, &number(12.0));
+    assert!(matches!(greeting, Ok(Value::String(s)) if s == "Happy result: 12"));
+}
+
+#[test]
+fn values_of_different_kinds_are_never_equal() {
+    assert!(matches!(number(1.0).binary(Operator::Equal, &string("1")), Ok(Value::Boolean(false))));
+    assert!(matches!(Value::Null.binary(Operator::NotEqual, &Value::Boolean(false)), Ok(Value::Boolean(true))));
+}
+
+#[test]
+fn operators_refuse_values_they_do_not_understand() {
+    let err = number(1.0).binary(Operator::Subtract, &string("one")).expect_err("numbers minus strings");
+    assert!(matches!(err, PubuError::TypeMismatch { right: Some("a string"), .. }), "{:?}", err);
+
+    let err = string("yes").unary(Operator::Not).expect_err("not a string");
+    assert!(matches!(err, PubuError::TypeMismatch { left: "a string", right: None, .. }), "{:?}", err);
+}
+
+#[test]
+fn doubt_superposition_and_whimsy_survive_operators() {
+    let doubtful = Value::Uncertain(Box::new(number(2.0)), 0.5);
+    let sum = doubtful.binary(Operator::Add, &Value::Uncertain(Box::new(number(1.0)), 0.5)).unwrap();
+    assert_eq!(sum.to_string(), "3 (probably, 25% sure)");
+
+    let cat = Value::Schrodinger(Box::new(number(1.0)), Box::new(number(2.0)));
+    assert_eq!(cat.binary(Operator::Multiply, &number(10.0)).unwrap().to_string(), "10 and/or 20");
+
+    let whim = Value::Whimsical("why?".to_string());
+    assert!(matches!(number(1.0).binary(Operator::Add, &whim), Ok(Value::Whimsical(s)) if s == "1 + why?"));
+}
+
+#[test]
+fn functions_return_early_with_their_own_arguments() {
+    let source = "dear_pubu;\nwhy?\ncelebration add(a, b) {\n  here_you_go a plus b;\n  here_you_go 0;\n}\nyay x is add(2, 3);";
+    assert_evaluates_to(source, 5.0);
+}
+
+#[test]
+fn calls_need_the_right_number_of_arguments() {
+    let err = run("dear_pubu;\nwhy?\ncelebration twice(a) {\n  here_you_go a times 2;\n}\nyay x is twice(1, 2);")
+        .expect_err("twice takes one argument");
+
+    assert!(matches!(err.inner(), PubuError::ArityMismatch { expected: 1, found: 2, .. }), "{:?}", err);
+}
+
+#[test]
+fn functions_change_variables_where_they_were_declared() {
+    let source = "dear_pubu;\nwhy?\nyay count is 0;\ncelebration bump() {\n  count is count plus 1;\n}\nbump();\nbump();\nyay x is count;";
+    assert_evaluates_to(source, 2.0);
+}
+
+#[test]
+fn loop_bodies_keep_their_own_declarations_to_themselves() {
+    let source = "dear_pubu;\nyay x is 1;\nagain_and_again 2 times {\n  yay x is 5;\n  x is 6;\n}\nyay y is x;";
+    assert_evaluates_to(source, 1.0);
+}
+
+#[test]
+fn branches_keep_their_own_declarations_to_themselves() {
+    let source = "dear_pubu;\nyay x is 1;\nperhaps x same_as 1 {\n  yay inner is 2;\n  x is inner;\n} else {\n  yay other is 3;\n}\nyay y is x;";
+    assert_evaluates_to(source, 2.0);
+
+    for read in ["inner", "other"] {
+        let source = format!("dear_pubu;\nyay x is 0;\nperhaps x same_as 0 {{\n  yay inner is 2;\n}} else {{\n  yay other is 3;\n}}\nyay y is {};", read);
+        let err = run(&source).expect_err("branch declarations end with the branch");
+        assert!(matches!(err.inner(), PubuError::UndefinedVariable(name) if name == read), "{:?}", err);
+    }
+}
+
+#[test]
+fn mood_and_lunar_blocks_declare_into_the_enclosing_scope() {
+    let source = "dear_pubu;\nwhy?\nhappy mood {\n  celebration seven() {\n    here_you_go 7;\n  }\n}\nlunar_phase new {\n  yay x is seven();\n} otherwise {\n  yay x is seven();\n}\nyay y is x;";
+    assert_evaluates_to(source, 7.0);
+}
+
+#[test]
+fn functions_can_be_passed_around_and_called_later() {
+    let source = "dear_pubu;\nwhy?\ncelebration twice(f, x) {\n  here_you_go f(f(x));\n}\ncelebration add_one(n) {\n  here_you_go n plus 1;\n}\nyay x is twice(add_one, 1);";
+    assert_evaluates_to(source, 3.0);
+}
+
+#[test]
+fn closures_remember_the_scope_they_were_declared_in() {
+    let source = "dear_pubu;\nwhy?\ncelebration make() {\n  yay secret is 7;\n  celebration reveal() {\n    here_you_go secret;\n  }\n  here_you_go reveal;\n}\nyay reveal is make();\nyay x is reveal();";
+    assert_evaluates_to(source, 7.0);
+}
+
+#[test]
+fn scopes_are_freed_even_when_their_functions_close_over_them() {
+    let scope = Environment::new().child();
+    let weak = scope.downgrade();
+    scope.declare("f", Value::Function(Function::new("f".to_string(), Vec::new(), Vec::new().into(), scope.clone())));
+
+    // Reading the function out and keeping it under another name holds on to nothing either
+    let f = scope.get("f").unwrap();
+    scope.declare("g", f);
+    drop(scope);
+
+    assert!(weak.upgrade().is_none(), "the scope and its functions keep each other alive");
+}
+
+#[test]
+fn reading_a_function_shares_its_body() {
+    let scope = Environment::new();
+    let body = parse("dear_pubu;\nyay x is 1;", Mood::Happy, Frontend::Pest).unwrap();
+    scope.declare("f", Value::Function(Function::new("f".to_string(), Vec::new(), vec![body].into(), scope.clone())));
+
+    match (scope.get("f"), scope.get("f")) {
+        (Some(Value::Function(first)), Some(Value::Function(second))) => {
+            assert!(std::rc::Rc::ptr_eq(&first.body, &second.body), "f's body was copied");
+        },
+        other => panic!("f is not a function: {:?}", other),
+    }
+}
+
+#[test]
+fn only_functions_can_be_called() {
+    let err = run("dear_pubu;\nyay x is 1;\nyay y is x();").expect_err("x is a number");
+
+    assert!(matches!(err.inner(), PubuError::NotCallable { kind: "a number", .. }), "{:?}", err);
+}
+
+#[test]
+fn conditionals_take_the_first_branch_that_holds() {
+    let chain = |n: i32| format!(
+        "dear_pubu;\nyay n is {};\nyay x is 0;\nperhaps n same_as 1 {{\n  x is 10;\n}} else perhaps n same_as 2 {{\n  x is 20;\n}} else {{\n  x is 30;\n}}\nyay y is x;",
+        n
+    );
+
+    assert_evaluates_to(&chain(1), 10.0);
+    assert_evaluates_to(&chain(2), 20.0);
+    assert_evaluates_to(&chain(3), 30.0);
+}
+
+#[test]
+fn every_value_has_a_truthiness() {
+    let mut rng = rand::thread_rng();
+    assert!(number(1.0).is_truthy(&mut rng));
+    assert!(!number(0.0).is_truthy(&mut rng));
+    assert!(string("no").is_truthy(&mut rng));
+    assert!(!string("").is_truthy(&mut rng));
+    assert!(!Value::Null.is_truthy(&mut rng));
+    assert!(Value::Uncertain(Box::new(Value::Boolean(true)), 1.0).is_truthy(&mut rng));
+    assert!(!Value::Uncertain(Box::new(Value::Boolean(true)), 0.0).is_truthy(&mut rng));
+    assert!(Value::Schrodinger(Box::new(number(1.0)), Box::new(string("yes"))).is_truthy(&mut rng));
+}
+
+#[test]
+fn loops_count_and_range() {
+    let counted = "dear_pubu;\nyay total is 0;\nagain_and_again 4 times {\n  total is total plus 2;\n}\nyay x is total;";
+    assert_evaluates_to(counted, 8.0);
+
+    let named = "dear_pubu;\nyay total is 0;\nagain_and_again i from 1 to 5 {\n  total is total plus i;\n}\nyay x is total;";
+    assert_evaluates_to(named, 10.0);
+
+    let unnamed = "dear_pubu;\nyay total is 0;\nagain_and_again from 0 to 3 {\n  total is total plus current;\n}\nyay x is total;";
+    assert_evaluates_to(unnamed, 3.0);
+}
+
+#[test]
+fn grumpy_runs_get_fed_up_with_top_level_loops() {
+    let source = "fine_pubu;\nrepeat_i_guess 1000 times {\n}";
+    let run_in = |mood, seed| {
+        let program = parse(source, mood, Frontend::Pest).unwrap();
+        Interpreter::new().with_mood(mood).with_context(Context::seeded(seed)).interpret(program)
+    };
+
+    // A thousand 30% chances to give up never all pass, so only a random failure can come first
+    for seed in 0..20 {
+        let err = run_in(Mood::Grumpy, seed).expect_err("PUBU got through a grumpy loop");
+        assert!(matches!(err.inner(), PubuError::BadMood | PubuError::RandomFailure), "{:?}", err);
+
+        let result = run_in(Mood::Happy, seed);
+        assert!(!matches!(&result, Err(err) if matches!(err.inner(), PubuError::BadMood)), "{:?}", result);
+    }
+}
+
+#[test]
+fn returning_is_one_way_out_of_an_infinite_loop() {
+    let source = "dear_pubu;\nwhy?\ncelebration forever() {\n  again_and_again {\n    here_you_go 5;\n  }\n}\nyay x is forever();";
+    assert_evaluates_to(source, 5.0);
+}
+
+#[test]
+fn break_and_continue_reach_through_nested_blocks() {
+    let continued = "dear_pubu;\nyay total is 0;\nagain_and_again 3 times {\n  total is total plus 1;\n  skip_this_one;\n  total is total plus 100;\n}\nyay x is total;";
+    assert_evaluates_to(continued, 3.0);
+
+    let broken = "dear_pubu;\nyay total is 0;\nagain_and_again {\n  total is total plus 1;\n  happy mood {\n    reality_distortion {\n      that_is_enough;\n    }\n  }\n}\nyay x is total;";
+    assert_evaluates_to(broken, 1.0);
+}
+
+#[test]
+fn breaking_outside_a_loop_fails() {
+    let err = run("dear_pubu;\nthat_is_enough;").expect_err("there is no loop");
+    assert!(matches!(err.inner(), PubuError::OutsideLoop), "{:?}", err);
+
+    let source = "dear_pubu;\nwhy?\ncelebration leave() {\n  that_is_enough;\n}\nagain_and_again {\n  leave();\n}";
+    let err = run(source).expect_err("the loop is not the function's");
+    assert!(matches!(err.inner(), PubuError::OutsideLoop), "{:?}", err);
+}
+
+fn run_with_max_call_depth(source: &str, max_call_depth: usize) -> Result<Value, PubuError> {
+    let context = Context::calm().with_max_call_depth(max_call_depth);
+    evaluate_in(source, Mood::Happy, Frontend::HandWritten, &context, &mut NullOutput)
+}
+
+#[test]
+fn runaway_recursion_hits_the_call_depth_limit() {
+    let source = "dear_pubu;\nwhy?\ncelebration forever(n) {\n  here_you_go forever(n plus 1);\n}\nforever(0);";
+
+    for max_call_depth in [16, DEFAULT_MAX_CALL_DEPTH] {
+        let err = run_with_max_call_depth(source, max_call_depth).expect_err("forever never ends");
+
+        assert!(
+            matches!(err.inner(), PubuError::RecursionTooDeep { name, depth } if name == "forever" && *depth == max_call_depth),
+            "{:?}",
+            err
+        );
+    }
+}
+
+#[test]
+fn recursion_runs_right_up_to_the_call_depth_limit() {
+    let sum = |n: usize| format!(
+        "dear_pubu;\nwhy?\ncelebration sum(n) {{\n  perhaps n bigger_than 0 {{\n    here_you_go n plus sum(n minus 1);\n  }}\n  here_you_go 0;\n}}\nsum({});",
+        n
+    );
+
+    // sum(n) makes n + 1 calls
+    let n = DEFAULT_MAX_CALL_DEPTH - 1;
+    assert!(matches!(run(&sum(n)), Ok(Value::Number(total)) if total == (n * (n + 1) / 2) as f64), "{:?}", run(&sum(n)));
+    let err = run(&sum(n + 1)).expect_err("sum makes one call too many");
+    assert!(matches!(err.inner(), PubuError::RecursionTooDeep { name, .. } if name == "sum"), "{:?}", err);
+}
+
+#[test]
+fn recursion_through_nested_blocks_counts_only_the_calls() {
+    // Every block between one call and the next takes its own share of the stack
+    let down = |n: usize| format!(
+        "dear_pubu;\nwhy?\ncelebration down(n) {{\n  happy mood {{\n    again_and_again 1 times {{\n      perhaps n bigger_than 0 {{\n        reality_distortion {{\n          here_you_go down(n minus 1) plus 1;\n        }}\n      }}\n    }}\n  }}\n  here_you_go 0;\n}}\ndown({});",
+        n
+    );
+
+    let n = DEFAULT_MAX_CALL_DEPTH - 1;
+    assert!(matches!(run(&down(n)), Ok(Value::Number(depth)) if depth == n as f64), "{:?}", run(&down(n)));
+    let err = run(&down(n + 1)).expect_err("down makes one call too many");
+    assert!(matches!(err.inner(), PubuError::RecursionTooDeep { name, .. } if name == "down"), "{:?}", err);
+}
+
+#[test]
+fn printing_goes_wherever_the_interpreter_is_told() {
+    let source = "dear_pubu;\nyay x is 2;\nprint(\"x is \" plus x);";
+    let output = BufferOutput::new();
+
+    let result = evaluate_in(source, Mood::Happy, Frontend::HandWritten, &Context::calm(), &mut output.clone());
+
+    assert!(result.is_ok(), "{:?}", result);
+    assert_eq!(output.lines(), ["x is 2"]);
+    assert!(output.chatter().iter().any(|line| line.contains("dear_pubu")), "{:?}", output.chatter());
+}
+
+#[test]
+fn chatter_arrives_as_plain_single_lines() {
+    // Some of these seeds get a critique
+    let source = "dear_pubu;\nwhy?\nyay x is 1;";
+    let output = BufferOutput::new();
+    for seed in 0..50 {
+        let _ = evaluate_in(source, Mood::Happy, Frontend::HandWritten, &Context::seeded(seed), &mut output.clone());
+    }
+
+    let chatter = output.chatter();
+    assert!(chatter.iter().any(|line| line.starts_with("AI CRITIC ANALYSIS")), "{:?}", chatter);
+    for line in &chatter {
+        assert!(!line.is_empty() && !line.contains('\n') && !line.contains('\x1b'), "{:?}", line);
+    }
+}
+
+#[test]
+fn the_same_seed_replays_the_same_run() {
+    let source = "dear_pubu;\nyay x is 1;\nagain_and_again i from 0 to 20 {\n  x is x plus i;\n}\nyay y is x times 2;";
+    let run = |seed| {
+        let output = BufferOutput::new();
+        let result = evaluate_in(source, Mood::Happy, Frontend::HandWritten, &Context::seeded(seed), &mut output.clone());
+        format!("{:?} {:?}", result, output.chatter())
+    };
+
+    let runs: Vec<String> = (0..20).map(run).collect();
+    for (seed, first) in runs.iter().enumerate() {
+        assert_eq!(&run(seed as u64), first, "seed {} changed its mind", seed);
+    }
+    // Different seeds should not all agree, or nothing is being rolled at all
+    assert!(runs.iter().any(|other| other != &runs[0]), "{:?}", runs);
+}
@@ -0,0 +1,58 @@
+use pubu::lunar::{Moon, Phase, SYNODIC_MONTH_DAYS};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+fn moon_at(unix_seconds: i64) -> Moon {
+    let time = if unix_seconds < 0 {
+        UNIX_EPOCH - Duration::from_secs(unix_seconds.unsigned_abs())
+    } else {
+        UNIX_EPOCH + Duration::from_secs(unix_seconds as u64)
+    };
+    Moon::at(time)
+}
+
+#[test]
+fn the_moon_matches_the_real_sky() {
+    let sightings = [
+        // 1969-07-29 02:45 UTC, the full moon after the first landing
+        (-13_468_500, Phase::Full),
+        // 2024-04-08 18:21 UTC, the new moon of the total solar eclipse
+        (1_712_600_460, Phase::New),
+        // 2024-04-15 19:13 UTC
+        (1_713_208_380, Phase::FirstQuarter),
+        // 2024-04-23 23:49 UTC
+        (1_713_916_140, Phase::Full),
+        // 2024-05-01 11:27 UTC
+        (1_714_562_820, Phase::LastQuarter),
+        // 2025-03-14 06:55 UTC, the full moon of the total lunar eclipse
+        (1_741_935_300, Phase::Full),
+    ];
+
+    for (timestamp, phase) in sightings {
+        assert_eq!(moon_at(timestamp).phase, phase, "at {}", timestamp);
+    }
+}
+
+#[test]
+fn illumination_follows_the_phase() {
+    let new = moon_at(1_712_600_460);
+    let full = moon_at(1_713_916_140);
+    let quarter = moon_at(1_713_208_380);
+
+    assert!(new.illumination < 0.01, "{:?}", new);
+    assert!(full.illumination > 0.99, "{:?}", full);
+    assert!((0.3..0.7).contains(&quarter.illumination), "{:?}", quarter);
+}
+
+#[test]
+fn a_month_goes_through_every_phase_in_order() {
+    let start = SystemTime::now();
+    let positions: Vec<usize> = (0..64)
+        .map(|step| Moon::at(start + Duration::from_secs_f64(SYNODIC_MONTH_DAYS * 86400.0 * step as f64 / 64.0)).phase)
+        .map(|phase| Phase::ALL.iter().position(|p| *p == phase).unwrap())
+        .collect();
+
+    for pair in positions.windows(2) {
+        assert!(pair[1] == pair[0] || pair[1] == (pair[0] + 1) % 8, "{:?}", positions);
+    }
+    assert!((0..8).all(|phase| positions.contains(&phase)), "{:?}", positions);
+}
@@ -0,0 +1,218 @@
+use pest::Parser as _;
+use pubu::ast::{LoopKind, LunarCondition, Node, Operator, SpannedNode};
+use pubu::lunar::Phase;
+use pubu::grammar::{PubuGrammar, Rule};
+use pubu::mood::Mood;
+use pubu::context::Context;
+use pubu::{parse_in, Frontend};
+
+mod common;
+use common::FRONTENDS;
+
+fn parse_statements(source: &str, mood: Mood, frontend: Frontend) -> Vec<SpannedNode> {
+    match parse_in(source, mood, frontend, &Context::calm()) {
+        Ok(program) => match program.node {
+            Node::Program(statements) => statements,
+            other => panic!("{:?} parsed {:?} to {:?}", frontend, source, other),
+        },
+        Err(err) => panic!("{:?} could not parse {:?}: {:?}", frontend, source, err),
+    }
+}
+
+#[test]
+fn compound_identifiers_are_compliments() {
+    for frontend in FRONTENDS {
+        let statements = parse_statements(
+            "dear_pubu_you_look_gorgeous_today;\ni_suppose_pubu_is_functional;",
+            Mood::Happy,
+            frontend,
+        );
+
+        assert!(
+            matches!(statements.as_slice(), [first, second]
+                if matches!((&first.node, &second.node), (Node::Compliment(first), Node::Compliment(second))
+                if first == "dear_pubu_you_look_gorgeous_today" && second == "i_suppose_pubu_is_functional")),
+            "{:?} parsed {:?}", frontend, statements
+        );
+    }
+}
+
+#[test]
+fn compliment_stems_can_still_name_variables() {
+    for frontend in FRONTENDS {
+        let statements = parse_statements("dear_pubu;\nsmart_total is 3;", Mood::Happy, frontend);
+
+        assert!(
+            matches!(&statements[1].node, Node::Assignment(name, _) if name == "smart_total"),
+            "{:?} parsed {:?}", frontend, statements
+        );
+    }
+}
+
+/// Every rule matched inside a loop statement with an empty body
+fn pest_loop_header(source: &str) -> Vec<Rule> {
+    PubuGrammar::parse(Rule::loop_statement, source)
+        .unwrap_or_else(|err| panic!("{}", err))
+        .flatten()
+        .map(|pair| pair.as_rule())
+        .collect()
+}
+
+#[test]
+fn times_before_a_loop_body_is_the_loop_marker() {
+    let header = pest_loop_header("again_and_again n times { }");
+
+    assert!(header.contains(&Rule::times_keyword), "{:?}", header);
+    assert!(!header.contains(&Rule::multiplication_operator), "{:?}", header);
+}
+
+#[test]
+fn times_between_operands_in_a_loop_header_multiplies() {
+    let header = pest_loop_header("again_and_again n times m { }");
+
+    assert!(header.contains(&Rule::multiplication_operator), "{:?}", header);
+    assert!(!header.contains(&Rule::times_keyword), "{:?}", header);
+
+    let header = pest_loop_header("again_and_again n times m times { }");
+
+    assert!(header.contains(&Rule::multiplication_operator), "{:?}", header);
+    assert!(header.contains(&Rule::times_keyword), "{:?}", header);
+}
+
+#[test]
+fn both_loop_readings_parse_in_happy_mood() {
+    let sources = [
+        "dear_pubu;\nagain_and_again n times { }",
+        "dear_pubu;\nagain_and_again n times m { }",
+        "dear_pubu;\nagain_and_again n times m times { }",
+    ];
+
+    for frontend in FRONTENDS {
+        for source in sources {
+            let statements = parse_statements(source, Mood::Happy, frontend);
+            assert!(matches!(&statements[1].node, Node::Loop(LoopKind::Counted(_), _)), "{:?} parsed {:?}", frontend, statements);
+        }
+    }
+}
+
+#[test]
+fn range_loops_name_their_count() {
+    let sources = [
+        ("dear_pubu;\nagain_and_again i from 0 to n { }", "i"),
+        ("dear_pubu;\nagain_and_again from 0 to n { }", "current"),
+    ];
+
+    for frontend in FRONTENDS {
+        for (source, name) in sources {
+            let statements = parse_statements(source, Mood::Happy, frontend);
+            assert!(
+                matches!(&statements[1].node, Node::Loop(LoopKind::Range { variable, .. }, _) if variable == name),
+                "{:?} parsed {:?}", frontend, statements
+            );
+        }
+
+        let statements = parse_statements("dear_pubu;\nagain_and_again { }", Mood::Happy, frontend);
+        assert!(matches!(&statements[1].node, Node::Loop(LoopKind::Infinite, _)), "{:?} parsed {:?}", frontend, statements);
+    }
+}
+
+#[test]
+fn times_outside_loop_headers_still_multiplies() {
+    for frontend in FRONTENDS {
+        let statements = parse_statements("dear_pubu;\nyay area is width times height;", Mood::Happy, frontend);
+
+        let value = match &statements[1].node {
+            Node::VariableDeclaration(_, value) => value.as_ref(),
+            other => panic!("{:?} parsed {:?}", frontend, other),
+        };
+        assert!(matches!(value.node, Node::BinaryOp(_, Operator::Multiply, _)), "{:?} parsed {:?}", frontend, value);
+    }
+}
+
+#[test]
+fn every_node_knows_where_it_was_written() {
+    let source = "dear_pubu;\nyay area is\n  width times height;";
+
+    for frontend in FRONTENDS {
+        let statements = parse_statements(source, Mood::Happy, frontend);
+        let declaration = &statements[1];
+        assert_eq!(&source[declaration.span.start..declaration.span.end], "yay area is\n  width times height;");
+        assert_eq!((declaration.span.line, declaration.span.column), (2, 1));
+
+        let value = match &declaration.node {
+            Node::VariableDeclaration(_, value) => value.as_ref(),
+            other => panic!("{:?} parsed {:?}", frontend, other),
+        };
+        assert_eq!(&source[value.span.start..value.span.end], "width times height");
+        assert_eq!((value.span.line, value.span.column), (3, 3));
+    }
+}
+
+#[test]
+fn every_mood_can_break_and_continue() {
+    for mood in Mood::ALL {
+        let keywords = mood.get_keywords();
+        let source = format!(
+            "dear_pubu;\n{} {{\n  {};\n  {};\n}}",
+            keywords.loop_construct, keywords.continue_loop, keywords.break_loop
+        );
+
+        for frontend in FRONTENDS {
+            let statements = parse_statements(&source, mood, frontend);
+            let Node::Loop(_, body) = &statements[1].node else {
+                panic!("{:?} parsed {:?}", frontend, statements);
+            };
+            assert!(matches!(&body[0].node, Node::Continue), "{:?} in {:?} parsed {:?}", frontend, mood, body);
+            assert!(matches!(&body[1].node, Node::Break), "{:?} in {:?} parsed {:?}", frontend, mood, body);
+        }
+    }
+}
+
+#[test]
+fn else_can_chain_another_conditional() {
+    let source = "dear_pubu;\nperhaps a {\n} else perhaps b {\n} else {\n}";
+
+    for frontend in FRONTENDS {
+        let statements = parse_statements(source, Mood::Happy, frontend);
+        let Node::IfStatement(_, _, Some(else_branch)) = &statements[1].node else {
+            panic!("{:?} parsed {:?}", frontend, statements);
+        };
+        assert!(
+            matches!(&else_branch[..], [chained] if matches!(&chained.node, Node::IfStatement(_, _, Some(_)))),
+            "{:?} parsed {:?}", frontend, else_branch
+        );
+        assert_eq!(&source[else_branch[0].span.start..else_branch[0].span.end], "perhaps b {\n} else {\n}");
+    }
+}
+
+#[test]
+fn print_is_a_statement_but_not_a_keyword() {
+    for frontend in FRONTENDS {
+        let statements = parse_statements("dear_pubu;\nprint(print);", Mood::Happy, frontend);
+
+        let value = match &statements[1].node {
+            Node::Print(value) => value.as_ref(),
+            other => panic!("{:?} parsed {:?}", frontend, other),
+        };
+        assert!(matches!(&value.node, Node::Identifier(name) if name == "print"), "{:?} parsed {:?}", frontend, value);
+    }
+}
+
+#[test]
+fn lunar_blocks_name_the_moon_they_wait_for() {
+    let sources = [
+        ("dear_pubu;\nlunar_phase { }", LunarCondition::Phase(Phase::Full), false),
+        ("dear_pubu;\nlunar_phase waning_crescent { } otherwise { }", LunarCondition::Phase(Phase::WaningCrescent), true),
+        ("dear_pubu;\nlunar_phase from 0.25 to 0.75 { }", LunarCondition::Illumination { from: 0.25, to: 0.75 }, false),
+    ];
+
+    for frontend in FRONTENDS {
+        for (source, expected, has_fallback) in sources {
+            let statements = parse_statements(source, Mood::Happy, frontend);
+            assert!(
+                matches!(&statements[1].node, Node::LunarPhaseBlock(condition, _, otherwise) if *condition == expected && otherwise.is_some() == has_fallback),
+                "{:?} parsed {:?}", frontend, statements
+            );
+        }
+    }
+}